@@ -0,0 +1,86 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use walkdir::WalkDir;
+
+// Crash-safe file writes. Content goes to a hidden temp file next to the target,
+// is flushed to disk and then renamed over the target, so readers only ever see
+// the old or the new file. A crash in between leaves the temp file behind, named
+// `.<file name>.onote-tmp-<pid>-<n>`.
+
+const TEMP_MARKER: &str = ".onote-tmp-";
+
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    path.with_file_name(format!(".{}{}{}-{}", name, TEMP_MARKER, std::process::id(), n))
+}
+
+// Persist the rename itself. Windows has no directory handles to sync.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Drop-in replacement for `fs::write` that never leaves a truncated file
+pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let tmp = temp_path(path);
+
+    let result = (|| {
+        let mut file = File::create_new(&tmp)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        drop(file);
+
+        fs::rename(&tmp, path)?;
+        match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => sync_dir(dir),
+            _ => Ok(()),
+        }
+    })();
+
+    if result.is_err() {
+        fs::remove_file(&tmp).ok();
+    }
+    result
+}
+
+/// The file an interrupted write was meant to replace, if `path` is a temp file
+pub fn target_of(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_str()?;
+    let (target, _) = name.strip_prefix('.')?.split_once(TEMP_MARKER)?;
+    if target.is_empty() {
+        return None;
+    }
+    Some(path.with_file_name(target))
+}
+
+// Temp files of this process may belong to a write that is still running
+fn is_ours(path: &Path) -> bool {
+    let own = format!("{}{}-", TEMP_MARKER, std::process::id());
+    path.file_name()
+        .map(|n| n.to_string_lossy().contains(&own))
+        .unwrap_or(false)
+}
+
+/// Temp files left behind by interrupted writes anywhere under `root`
+pub fn find_leftovers(root: &Path) -> Vec<PathBuf> {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| target_of(e.path()).is_some() && !is_ours(e.path()))
+        .map(|e| e.into_path())
+        .collect()
+}
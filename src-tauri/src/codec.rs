@@ -0,0 +1,432 @@
+use crate::commands::settings::AudioFormat;
+use hound::{WavSpec, WavWriter};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+
+// Whisper expects 16 kHz mono input, so every recording is stored that way
+pub const WHISPER_SAMPLE_RATE: u32 = 16000;
+
+// Opus frames are 20 ms; granule positions are always counted at 48 kHz
+const OPUS_FRAME_SIZE: usize = WHISPER_SAMPLE_RATE as usize / 50;
+const OPUS_GRANULE_PER_FRAME: u64 = 960;
+const OPUS_GRANULE_PER_SAMPLE: u64 = 48000 / WHISPER_SAMPLE_RATE as u64;
+const OPUS_PRE_SKIP: u16 = 312;
+const OPUS_BITRATE: i32 = 24000;
+const OPUS_SERIAL: u32 = 0x6f6e_6f74;
+
+impl AudioFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "wav",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Opus => "opus",
+        }
+    }
+}
+
+/// Average interleaved channels down to mono
+pub fn downmix(samples: Vec<f32>, channels: usize) -> Vec<f32> {
+    if channels <= 1 {
+        return samples;
+    }
+
+    samples
+        .chunks(channels)
+        .map(|chunk| chunk.iter().sum::<f32>() / chunk.len() as f32)
+        .collect()
+}
+
+/// Simple linear resampling
+pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let ratio = from_rate as f64 / to_rate as f64;
+    let new_len = (samples.len() as f64 / ratio) as usize;
+    let mut result = Vec::with_capacity(new_len);
+
+    for i in 0..new_len {
+        let src_idx = i as f64 * ratio;
+        let idx = src_idx as usize;
+        let frac = src_idx - idx as f64;
+
+        let sample = if idx + 1 < samples.len() {
+            samples[idx] * (1.0 - frac as f32) + samples[idx + 1] * frac as f32
+        } else {
+            samples[idx.min(samples.len() - 1)]
+        };
+
+        result.push(sample);
+    }
+
+    result
+}
+
+fn to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
+
+/// Write 16 kHz mono samples to `path` in the given format
+pub fn encode(path: &Path, samples: &[f32], format: AudioFormat) -> Result<(), String> {
+    match format {
+        AudioFormat::Wav => write_wav(path, samples),
+        AudioFormat::Flac => write_flac(path, samples),
+        AudioFormat::Opus => write_opus(path, samples),
+    }
+}
+
+pub fn write_wav(path: &Path, samples: &[f32]) -> Result<(), String> {
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate: WHISPER_SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+
+    let mut writer = WavWriter::create(path, spec).map_err(|e| e.to_string())?;
+
+    for sample in samples {
+        writer.write_sample(to_i16(*sample)).map_err(|e| e.to_string())?;
+    }
+
+    writer.finalize().map_err(|e| e.to_string())
+}
+
+fn write_flac(path: &Path, samples: &[f32]) -> Result<(), String> {
+    use flacenc::component::BitRepr;
+    use flacenc::error::Verify;
+
+    let pcm: Vec<i32> = samples.iter().map(|s| to_i16(*s) as i32).collect();
+
+    let config = flacenc::config::Encoder::default()
+        .into_verified()
+        .map_err(|(_, e)| format!("Invalid FLAC config: {:?}", e))?;
+    let source = flacenc::source::MemSource::from_samples(&pcm, 1, 16, WHISPER_SAMPLE_RATE as usize);
+    let stream = flacenc::encode_with_fixed_block_size(&config, source, config.block_size)
+        .map_err(|e| format!("FLAC encoding failed: {:?}", e))?;
+
+    let mut sink = flacenc::bitsink::ByteSink::new();
+    stream
+        .write(&mut sink)
+        .map_err(|_| "FLAC encoding failed: could not write stream".to_string())?;
+
+    fs::write(path, sink.as_slice()).map_err(|e| e.to_string())
+}
+
+// RFC 7845 identification header for a mono 16 kHz stream
+fn opus_head() -> Vec<u8> {
+    let mut head = b"OpusHead".to_vec();
+    head.push(1); // version
+    head.push(1); // channels
+    head.extend_from_slice(&OPUS_PRE_SKIP.to_le_bytes());
+    head.extend_from_slice(&WHISPER_SAMPLE_RATE.to_le_bytes());
+    head.extend_from_slice(&0i16.to_le_bytes()); // output gain
+    head.push(0); // channel mapping family
+    head
+}
+
+fn opus_tags() -> Vec<u8> {
+    let vendor = b"opnotes";
+    let mut tags = b"OpusTags".to_vec();
+    tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    tags.extend_from_slice(vendor);
+    tags.extend_from_slice(&0u32.to_le_bytes()); // no user comments
+    tags
+}
+
+fn write_opus(path: &Path, samples: &[f32]) -> Result<(), String> {
+    use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+
+    let mut encoder = opus::Encoder::new(WHISPER_SAMPLE_RATE, opus::Channels::Mono, opus::Application::Voip)
+        .map_err(|e| e.to_string())?;
+    encoder
+        .set_bitrate(opus::Bitrate::Bits(OPUS_BITRATE))
+        .map_err(|e| e.to_string())?;
+
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut writer = PacketWriter::new(BufWriter::new(file));
+
+    writer
+        .write_packet(opus_head().into_boxed_slice(), OPUS_SERIAL, PacketWriteEndInfo::EndPage, 0)
+        .map_err(|e| e.to_string())?;
+    writer
+        .write_packet(opus_tags().into_boxed_slice(), OPUS_SERIAL, PacketWriteEndInfo::EndPage, 0)
+        .map_err(|e| e.to_string())?;
+
+    let frame_count = samples.len().div_ceil(OPUS_FRAME_SIZE).max(1);
+    let mut packet = vec![0u8; 4000];
+    let mut granule = OPUS_PRE_SKIP as u64;
+
+    for i in 0..frame_count {
+        // The last frame is zero-padded to a full 20 ms
+        let mut frame = [0f32; OPUS_FRAME_SIZE];
+        let start = i * OPUS_FRAME_SIZE;
+        let end = (start + OPUS_FRAME_SIZE).min(samples.len());
+        if start < end {
+            frame[..end - start].copy_from_slice(&samples[start..end]);
+        }
+
+        let len = encoder
+            .encode_float(&frame, &mut packet)
+            .map_err(|e| e.to_string())?;
+
+        granule += OPUS_GRANULE_PER_FRAME;
+        let end_info = if i + 1 == frame_count {
+            // The final granule marks where the audio ends, so players drop
+            // the padding of the last frame
+            granule = OPUS_PRE_SKIP as u64 + samples.len() as u64 * OPUS_GRANULE_PER_SAMPLE;
+            PacketWriteEndInfo::EndStream
+        } else {
+            PacketWriteEndInfo::NormalPacket
+        };
+
+        writer
+            .write_packet(packet[..len].to_vec().into_boxed_slice(), OPUS_SERIAL, end_info, granule)
+            .map_err(|e| e.to_string())?;
+    }
+
+    // Recordings are encoded straight from memory, so a failed final write has
+    // to be reported rather than lost in `Drop`
+    let file = writer.into_inner().into_inner().map_err(|e| e.to_string())?;
+    file.sync_all().map_err(|e| e.to_string())
+}
+
+/// SHA-256 of a file's contents as lowercase hex
+pub fn hash_file(path: &Path) -> Result<String, String> {
+    use sha2::{Digest, Sha256};
+    use std::io::Read;
+
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Decode any supported recording into 16 kHz mono samples for Whisper
+pub fn decode(path: &Path) -> Result<Vec<f32>, String> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+
+    // .ogg files from phones are usually Opus; fall back to Vorbis via symphonia
+    let (samples, sample_rate) = match ext.as_str() {
+        "opus" => (read_opus(path)?, WHISPER_SAMPLE_RATE),
+        "ogg" => match read_opus(path) {
+            Ok(samples) => (samples, WHISPER_SAMPLE_RATE),
+            Err(_) => read_with_symphonia(path)?,
+        },
+        _ => read_with_symphonia(path)?,
+    };
+
+    Ok(resample(&samples, sample_rate, WHISPER_SAMPLE_RATE))
+}
+
+fn read_opus(path: &Path) -> Result<Vec<f32>, String> {
+    use ogg::reading::PacketReader;
+
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut reader = PacketReader::new(std::io::BufReader::new(file));
+    let mut decoder = opus::Decoder::new(WHISPER_SAMPLE_RATE, opus::Channels::Mono)
+        .map_err(|e| e.to_string())?;
+
+    let mut samples = Vec::new();
+    let mut channels = 1;
+    let mut pre_skip = 0;
+    let mut skip_48k = 0;
+    let mut end_granule = None;
+    let mut packet_index = 0;
+    // Large enough for the longest Opus frame (120 ms)
+    let mut buffer = vec![0f32; 5760 * 2];
+
+    while let Some(packet) = reader.read_packet().map_err(|e| e.to_string())? {
+        packet_index += 1;
+
+        // Header packets: OpusHead then OpusTags
+        if packet_index == 1 {
+            if packet.data.len() < 19 || !packet.data.starts_with(b"OpusHead") {
+                return Err("Not an Ogg Opus file".to_string());
+            }
+            channels = packet.data[9].max(1) as usize;
+            skip_48k = u16::from_le_bytes([packet.data[10], packet.data[11]]) as usize;
+            pre_skip = skip_48k * WHISPER_SAMPLE_RATE as usize / 48000;
+            if channels > 1 {
+                decoder = opus::Decoder::new(WHISPER_SAMPLE_RATE, opus::Channels::Stereo)
+                    .map_err(|e| e.to_string())?;
+            }
+            continue;
+        }
+        if packet_index == 2 {
+            continue;
+        }
+
+        if packet.last_in_stream() {
+            end_granule = Some(packet.absgp_page());
+        }
+        let frames = decoder
+            .decode_float(&packet.data, &mut buffer, false)
+            .map_err(|e| e.to_string())?;
+        samples.extend_from_slice(&buffer[..frames * channels.min(2)]);
+    }
+
+    let mono = downmix(samples, channels.min(2));
+    let mut mono: Vec<f32> = mono.into_iter().skip(pre_skip).collect();
+    // Drop the padding after the end the stream gives
+    if let Some(end) = end_granule {
+        mono.truncate((end.saturating_sub(skip_48k as u64) / OPUS_GRANULE_PER_SAMPLE) as usize);
+    }
+    Ok(mono)
+}
+
+fn read_with_symphonia(path: &Path) -> Result<(Vec<f32>, u32), String> {
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+    use symphonia::core::errors::Error as DecodeError;
+    use symphonia::core::formats::FormatOptions;
+    use symphonia::core::io::MediaSourceStream;
+    use symphonia::core::meta::MetadataOptions;
+    use symphonia::core::probe::Hint;
+
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    let probed = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| format!("Unsupported audio file: {}", e))?;
+    let mut format = probed.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| "No audio track found".to_string())?;
+    let track_id = track.id;
+    let sample_rate = track
+        .codec_params
+        .sample_rate
+        .ok_or_else(|| "Unknown sample rate".to_string())?;
+
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| format!("Unsupported codec: {}", e))?;
+
+    let mut samples = Vec::new();
+    let mut channels = 1;
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(DecodeError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.to_string()),
+        };
+
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        match decoder.decode(&packet) {
+            Ok(decoded) => {
+                let spec = *decoded.spec();
+                channels = spec.channels.count();
+                let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+                buffer.copy_interleaved_ref(decoded);
+                samples.extend_from_slice(buffer.samples());
+            }
+            // Skip corrupt packets rather than failing the whole file
+            Err(DecodeError::DecodeError(e)) => eprintln!("Skipping undecodable packet: {}", e),
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+
+    Ok((downmix(samples, channels), sample_rate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    // Half a second of a 440 Hz tone, not a whole number of Opus frames
+    fn tone() -> Vec<f32> {
+        (0..8050)
+            .map(|i| (i as f32 * 440.0 * std::f32::consts::TAU / WHISPER_SAMPLE_RATE as f32).sin() * 0.5)
+            .collect()
+    }
+
+    fn round_trip(format: AudioFormat) -> (Vec<f32>, Vec<f32>) {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(format!("tone.{}", format.extension()));
+        let samples = tone();
+        encode(&path, &samples, format).unwrap();
+        (samples, decode(&path).unwrap())
+    }
+
+    fn max_error(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| (x - y).abs()).fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn wav_round_trip() {
+        let (samples, decoded) = round_trip(AudioFormat::Wav);
+        assert_eq!(decoded.len(), samples.len());
+        assert!(max_error(&samples, &decoded) < 1e-3);
+    }
+
+    #[test]
+    fn flac_round_trip() {
+        let (samples, decoded) = round_trip(AudioFormat::Flac);
+        assert_eq!(decoded.len(), samples.len());
+        assert!(max_error(&samples, &decoded) < 1e-3);
+    }
+
+    #[test]
+    fn opus_round_trip_drops_the_padding() {
+        let (samples, decoded) = round_trip(AudioFormat::Opus);
+        // Lossy, so only the length is exact
+        assert_eq!(decoded.len(), samples.len());
+        let energy = |s: &[f32]| s.iter().map(|x| x * x).sum::<f32>() / s.len() as f32;
+        assert!((energy(&decoded) - energy(&samples)).abs() < energy(&samples) * 0.5);
+    }
+
+    #[test]
+    fn downmix_averages_channels() {
+        assert_eq!(downmix(vec![1.0, 0.0, 0.5, -0.5], 2), [0.5, 0.0]);
+        assert_eq!(downmix(vec![0.25, 0.5, 0.75], 3), [0.5]);
+        assert_eq!(downmix(vec![0.1, 0.2], 1), [0.1, 0.2]);
+    }
+
+    #[test]
+    fn resample_keeps_duration_and_shape() {
+        let ramp: Vec<f32> = (0..480).map(|i| i as f32 / 480.0).collect();
+
+        let down = resample(&ramp, 48000, 16000);
+        assert_eq!(down.len(), 160);
+        assert!(down.iter().enumerate().all(|(i, s)| (s - i as f32 * 3.0 / 480.0).abs() < 1e-6));
+
+        let up = resample(&ramp[..4], 8000, 16000);
+        assert_eq!(up.len(), 8);
+        // Halfway points are interpolated, the end holds the last sample
+        assert!((up[1] - 0.5 / 480.0).abs() < 1e-6);
+        assert_eq!(up[7], ramp[3]);
+
+        assert_eq!(resample(&ramp, 16000, 16000), ramp);
+        assert!(resample(&[], 44100, 16000).is_empty());
+    }
+}
@@ -1,16 +1,17 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Device, SampleFormat, SampleRate, StreamConfig};
-use hound::{WavSpec, WavWriter};
 use ringbuf::traits::{Consumer, Producer, Split};
 use ringbuf::HeapRb;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use tauri::State;
 
+use crate::codec;
+use crate::commands::settings::load_settings;
 use crate::AppState;
 
 // Audio device info for frontend
@@ -32,10 +33,18 @@ pub struct RecordingState {
 static RECORDING_ACTIVE: AtomicBool = AtomicBool::new(false);
 static STOP_SIGNAL: AtomicBool = AtomicBool::new(false);
 
+// File extensions written to the audio directory
+pub const RECORDING_EXTENSIONS: &[&str] = &["wav", "flac", "opus"];
+
+// Recordings live in a hidden folder inside the notes directory
+pub fn audio_dir(notes_dir: &Path) -> PathBuf {
+    notes_dir.join(".audio")
+}
+
 // Get audio data directory
 fn get_audio_dir(state: &State<AppState>) -> PathBuf {
     let notes_dir = state.notes_dir.lock().unwrap().clone();
-    let audio_dir = notes_dir.join(".audio");
+    let audio_dir = audio_dir(&notes_dir);
     fs::create_dir_all(&audio_dir).ok();
     audio_dir
 }
@@ -362,48 +371,24 @@ pub fn stop_recording(state: State<AppState>) -> Result<String, String> {
     println!("Collected {} samples at {}Hz, {} channels", samples.len(), sample_rate, channels);
 
     // Convert to mono if stereo
-    let mono_samples: Vec<f32> = if channels > 1 {
-        samples
-            .chunks(channels as usize)
-            .map(|chunk| chunk.iter().sum::<f32>() / chunk.len() as f32)
-            .collect()
-    } else {
-        samples
-    };
+    let mono_samples = codec::downmix(samples, channels as usize);
 
     // Resample to 16kHz if needed
-    let final_samples = if sample_rate != 16000 {
-        resample(&mono_samples, sample_rate, 16000)
-    } else {
-        mono_samples
-    };
+    let final_samples = codec::resample(&mono_samples, sample_rate, codec::WHISPER_SAMPLE_RATE);
 
     println!("Final samples: {} (resampled to 16kHz)", final_samples.len());
 
-    // Save to WAV file
+    // Save in the configured format
+    let format = load_settings().audio_format;
     let audio_dir = get_audio_dir(&state);
     let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-    let wav_path = audio_dir.join(format!("recording_{}.wav", timestamp));
-
-    let spec = WavSpec {
-        channels: 1,
-        sample_rate: 16000,
-        bits_per_sample: 16,
-        sample_format: hound::SampleFormat::Int,
-    };
+    let audio_path = audio_dir.join(format!("recording_{}.{}", timestamp, format.extension()));
 
-    let mut writer = WavWriter::create(&wav_path, spec).map_err(|e| e.to_string())?;
-
-    for sample in &final_samples {
-        let s = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
-        writer.write_sample(s).map_err(|e| e.to_string())?;
-    }
+    codec::encode(&audio_path, &final_samples, format)?;
 
-    writer.finalize().map_err(|e| e.to_string())?;
+    println!("Saved recording to: {:?}", audio_path);
 
-    println!("Saved recording to: {:?}", wav_path);
-
-    Ok(wav_path.to_string_lossy().to_string())
+    Ok(audio_path.to_string_lossy().to_string())
 }
 
 #[tauri::command]
@@ -423,33 +408,6 @@ pub fn cancel_recording(state: State<AppState>) -> Result<(), String> {
     Ok(())
 }
 
-// Simple linear resampling
-fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
-    if from_rate == to_rate {
-        return samples.to_vec();
-    }
-
-    let ratio = from_rate as f64 / to_rate as f64;
-    let new_len = (samples.len() as f64 / ratio) as usize;
-    let mut result = Vec::with_capacity(new_len);
-
-    for i in 0..new_len {
-        let src_idx = i as f64 * ratio;
-        let idx = src_idx as usize;
-        let frac = src_idx - idx as f64;
-
-        let sample = if idx + 1 < samples.len() {
-            samples[idx] * (1.0 - frac as f32) + samples[idx + 1] * frac as f32
-        } else {
-            samples[idx.min(samples.len() - 1)]
-        };
-
-        result.push(sample);
-    }
-
-    result
-}
-
 #[tauri::command]
 pub fn is_recording() -> bool {
     RECORDING_ACTIVE.load(Ordering::SeqCst)
@@ -0,0 +1,254 @@
+use crate::atomic;
+use crate::codec;
+use crate::commands::notes::{meta_dir, slugify, unique_note_path};
+use crate::commands::whisper::{run_transcription, TranscribeOptions};
+use crate::paths;
+use crate::AppState;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tauri::{AppHandle, Emitter, State};
+use walkdir::WalkDir;
+
+// Formats symphonia or the Opus reader can decode
+const IMPORT_EXTENSIONS: &[&str] = &["wav", "flac", "opus", "ogg", "mp3", "m4a", "aac"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    Transcribing,
+    Done,
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchProgress {
+    pub current: usize,
+    pub total: usize,
+    pub file: String,
+    pub status: BatchStatus,
+    pub note_path: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchFailure {
+    pub file: String,
+    pub error: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BatchSummary {
+    pub created: Vec<String>,
+    pub skipped: usize,
+    pub failed: Vec<BatchFailure>,
+}
+
+// One imported source file, keyed by content hash in the import cache
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ImportRecord {
+    source: String,
+    note: String,
+    imported_at: String,
+}
+
+fn import_cache_path(notes_dir: &Path) -> PathBuf {
+    meta_dir(notes_dir).join("imports.json")
+}
+
+fn load_import_cache(notes_dir: &Path) -> HashMap<String, ImportRecord> {
+    fs::read_to_string(import_cache_path(notes_dir))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_import_cache(notes_dir: &Path, cache: &HashMap<String, ImportRecord>) -> Result<(), String> {
+    let path = import_cache_path(notes_dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let content = serde_json::to_string_pretty(cache).map_err(|e| e.to_string())?;
+    atomic::write(&path, content).map_err(|e| e.to_string())
+}
+
+// Audio files under `dir`, oldest first so notes are created in recording order
+fn find_audio_files(dir: &Path) -> Vec<(PathBuf, SystemTime)> {
+    let mut files: Vec<(PathBuf, SystemTime)> = WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| {
+            e.path()
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| IMPORT_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+                .unwrap_or(false)
+        })
+        .map(|e| {
+            let modified = e
+                .metadata()
+                .ok()
+                .and_then(|m| m.modified().ok())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            (e.into_path(), modified)
+        })
+        .collect();
+
+    files.sort_by_key(|(_, modified)| *modified);
+    files
+}
+
+fn title_from_file(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let title = stem.replace(['_', '-'], " ").trim().to_string();
+    if title.is_empty() {
+        "Voice memo".to_string()
+    } else {
+        title
+    }
+}
+
+fn source_link(path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    match url::Url::from_file_path(path) {
+        Ok(url) => format!("[{}](<{}>)", name, url),
+        Err(_) => name,
+    }
+}
+
+fn write_note(
+    folder_path: &Path,
+    source: &Path,
+    recorded: SystemTime,
+    text: &str,
+) -> Result<PathBuf, String> {
+    let recorded: DateTime<Local> = recorded.into();
+    let title = title_from_file(source);
+
+    let note_path = unique_note_path(
+        folder_path,
+        &recorded.format("%Y-%m-%d").to_string(),
+        &slugify(&title),
+    );
+
+    let content = format!(
+        "# {}\n\n*Recorded {}* · Source: {}\n\n{}\n",
+        title,
+        recorded.format("%Y-%m-%d %H:%M"),
+        source_link(source),
+        text
+    );
+
+    atomic::write(&note_path, content).map_err(|e| e.to_string())?;
+    Ok(note_path)
+}
+
+/// Transcribe every audio file under `source_dir` into its own note in `target_folder`
+#[tauri::command]
+pub async fn batch_transcribe(
+    source_dir: String,
+    target_folder: String,
+    options: Option<TranscribeOptions>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<BatchSummary, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let options = options.unwrap_or_else(|| TranscribeOptions {
+        model_id: state.selected_model.lock().unwrap().clone(),
+        language: None,
+        prompt: None,
+    });
+
+    let source_dir = PathBuf::from(&source_dir);
+    if !source_dir.is_dir() {
+        return Err(format!("Not a folder: {}", source_dir.display()));
+    }
+
+    let folder_path = paths::resolve(&notes_dir, &target_folder)?;
+    fs::create_dir_all(&folder_path).map_err(|e| e.to_string())?;
+
+    let files = find_audio_files(&source_dir);
+    let total = files.len();
+    let mut cache = load_import_cache(&notes_dir);
+    let mut summary = BatchSummary::default();
+
+    println!("Batch transcribing {} files from {:?}", total, source_dir);
+
+    for (i, (path, modified)) in files.into_iter().enumerate() {
+        let file = path.to_string_lossy().to_string();
+        let emit = |status: BatchStatus, note_path: Option<String>, error: Option<String>| {
+            let progress = BatchProgress {
+                current: i + 1,
+                total,
+                file: file.clone(),
+                status,
+                note_path,
+                error,
+            };
+            app.emit("batch-transcribe-progress", &progress).ok();
+        };
+
+        let hash = match codec::hash_file(&path) {
+            Ok(hash) => hash,
+            Err(error) => {
+                emit(BatchStatus::Failed, None, Some(error.clone()));
+                summary.failed.push(BatchFailure { file, error });
+                continue;
+            }
+        };
+
+        if let Some(record) = cache.get(&hash) {
+            emit(BatchStatus::Skipped, Some(record.note.clone()), None);
+            summary.skipped += 1;
+            continue;
+        }
+
+        emit(BatchStatus::Transcribing, None, None);
+
+        let result = match run_transcription(&file, &options).await {
+            Ok(transcript) => write_note(&folder_path, &path, modified, &transcript.text),
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(note_path) => {
+                let rel_path = paths::to_relative(&notes_dir, &note_path);
+
+                cache.insert(
+                    hash,
+                    ImportRecord {
+                        source: file.clone(),
+                        note: rel_path.clone(),
+                        imported_at: Local::now().to_rfc3339(),
+                    },
+                );
+                // Persist after every file so an interrupted batch resumes where it stopped.
+                // The note exists either way, so a failed save doesn't stop the batch.
+                if let Err(e) = save_import_cache(&notes_dir, &cache) {
+                    eprintln!("Failed to save import cache: {}", e);
+                }
+
+                emit(BatchStatus::Done, Some(rel_path.clone()), None);
+                summary.created.push(rel_path);
+            }
+            Err(error) => {
+                eprintln!("Batch transcription failed for {}: {}", file, error);
+                emit(BatchStatus::Failed, None, Some(error.clone()));
+                summary.failed.push(BatchFailure { file, error });
+            }
+        }
+    }
+
+    Ok(summary)
+}
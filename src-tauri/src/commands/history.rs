@@ -0,0 +1,353 @@
+use crate::atomic;
+use crate::commands::notes::{content_version, meta_dir, NoteContent};
+use crate::diff::{self, DiffChunk};
+use crate::frontmatter;
+use crate::paths;
+use crate::AppState;
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+// Local history lives in <notes_dir>/.onote/history:
+//   objects/<sha256>    note contents, stored once however many versions share them
+//   index/<sha256>.json versions of one note, keyed by a hash of its relative path
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteVersion {
+    pub id: String,
+    pub saved_at: String,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct VersionIndex {
+    path: String,
+    versions: Vec<NoteVersion>,
+}
+
+fn history_dir(notes_dir: &Path) -> PathBuf {
+    meta_dir(notes_dir).join("history")
+}
+
+fn objects_dir(notes_dir: &Path) -> PathBuf {
+    history_dir(notes_dir).join("objects")
+}
+
+fn index_dir(notes_dir: &Path) -> PathBuf {
+    history_dir(notes_dir).join("index")
+}
+
+fn hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+fn index_path(notes_dir: &Path, rel_path: &str) -> PathBuf {
+    index_dir(notes_dir).join(format!("{}.json", hash(rel_path)))
+}
+
+fn load_index(notes_dir: &Path, rel_path: &str) -> VersionIndex {
+    fs::read_to_string(index_path(notes_dir, rel_path))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_else(|| VersionIndex {
+            path: rel_path.to_string(),
+            versions: Vec::new(),
+        })
+}
+
+fn save_index(notes_dir: &Path, index: &VersionIndex) -> Result<(), String> {
+    let path = index_path(notes_dir, &index.path);
+    if index.versions.is_empty() {
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| e.to_string())?;
+        }
+        return Ok(());
+    }
+
+    fs::create_dir_all(index_dir(notes_dir)).map_err(|e| e.to_string())?;
+    let content = serde_json::to_string_pretty(index).map_err(|e| e.to_string())?;
+    atomic::write(&path, content).map_err(|e| e.to_string())
+}
+
+fn read_object(notes_dir: &Path, id: &str) -> Result<String, String> {
+    // Ids come from the webview; only accept what `hash` produces
+    if id.len() != 64 || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid version id: {}", id));
+    }
+    fs::read_to_string(objects_dir(notes_dir).join(id)).map_err(|_| format!("Version not found: {}", id))
+}
+
+// Content of version `id` of the note at `full_path`. The store is shared by
+// all notes, so ids from other notes' histories are refused.
+fn read_note_version(notes_dir: &Path, full_path: &Path, id: &str) -> Result<String, String> {
+    let index = load_index(notes_dir, &paths::to_relative(notes_dir, full_path));
+    if !index.versions.iter().any(|v| v.id == id) {
+        return Err(format!("Version not found: {}", id));
+    }
+    read_object(notes_dir, id)
+}
+
+// Which versions survive thinning: every version from the last hour, the newest
+// per hour for the last day, and the newest per day before that. Versions with
+// an unreadable timestamp are kept rather than guessed at.
+fn thin(versions: &[NoteVersion], now: DateTime<Local>) -> Vec<bool> {
+    let mut seen = HashSet::new();
+    let mut keep = vec![false; versions.len()];
+
+    for (i, version) in versions.iter().enumerate().rev() {
+        let saved_at = match DateTime::parse_from_rfc3339(&version.saved_at) {
+            Ok(t) => t.with_timezone(&Local),
+            Err(_) => {
+                keep[i] = true;
+                continue;
+            }
+        };
+        let age = now.signed_duration_since(saved_at);
+
+        keep[i] = if age < Duration::hours(1) {
+            true
+        } else if age < Duration::days(1) {
+            seen.insert(saved_at.format("h %Y-%m-%d %H").to_string())
+        } else {
+            seen.insert(saved_at.format("d %Y-%m-%d").to_string())
+        };
+    }
+
+    keep
+}
+
+// Delete objects that no index refers to any more
+fn collect_garbage(notes_dir: &Path, candidates: HashSet<String>) {
+    if candidates.is_empty() {
+        return;
+    }
+
+    let mut unreferenced = candidates;
+    if let Ok(entries) = fs::read_dir(index_dir(notes_dir)) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            let index: Option<VersionIndex> = fs::read_to_string(entry.path())
+                .ok()
+                .and_then(|content| serde_json::from_str(&content).ok());
+            if let Some(index) = index {
+                for version in &index.versions {
+                    unreferenced.remove(&version.id);
+                }
+            }
+        }
+    }
+
+    for id in unreferenced {
+        fs::remove_file(objects_dir(notes_dir).join(&id)).ok();
+    }
+}
+
+/// Record `content` as a version of the note at `full_path`, typically the
+/// content a save is about to overwrite
+pub fn snapshot(notes_dir: &Path, full_path: &Path, content: &str) -> Result<(), String> {
+    let rel_path = paths::to_relative(notes_dir, full_path);
+    let id = hash(content);
+    let mut index = load_index(notes_dir, &rel_path);
+
+    if index.versions.last().map(|v| v.id == id).unwrap_or(false) {
+        return Ok(());
+    }
+
+    let object = objects_dir(notes_dir).join(&id);
+    if !object.exists() {
+        fs::create_dir_all(objects_dir(notes_dir)).map_err(|e| e.to_string())?;
+        atomic::write(&object, content).map_err(|e| e.to_string())?;
+    }
+
+    let now = Local::now();
+    index.versions.push(NoteVersion {
+        id,
+        saved_at: now.to_rfc3339(),
+        size_bytes: content.len() as u64,
+    });
+
+    let keep = thin(&index.versions, now);
+    let mut dropped = HashSet::new();
+    let mut i = 0;
+    index.versions.retain(|v| {
+        let kept = keep[i];
+        i += 1;
+        if !kept {
+            dropped.insert(v.id.clone());
+        }
+        kept
+    });
+
+    save_index(notes_dir, &index)?;
+    collect_garbage(notes_dir, dropped);
+    Ok(())
+}
+
+/// Snapshot the note's current file content before it gets overwritten
+pub fn snapshot_file(notes_dir: &Path, full_path: &Path, new_content: &str) {
+    if let Ok(previous) = fs::read_to_string(full_path) {
+        if previous != new_content {
+            if let Err(e) = snapshot(notes_dir, full_path, &previous) {
+                eprintln!("Failed to record note history: {}", e);
+            }
+        }
+    }
+}
+
+/// Carry the history of moved notes over to their new paths (absolute old → new)
+pub fn move_entries(notes_dir: &Path, moves: &HashMap<PathBuf, PathBuf>) {
+    for (old, new) in moves {
+        let mut index = load_index(notes_dir, &paths::to_relative(notes_dir, old));
+        if index.versions.is_empty() {
+            continue;
+        }
+
+        let old_index = index_path(notes_dir, &index.path);
+        index.path = paths::to_relative(notes_dir, new);
+        match save_index(notes_dir, &index) {
+            Ok(()) => {
+                fs::remove_file(old_index).ok();
+            }
+            Err(e) => eprintln!("Failed to move note history: {}", e),
+        }
+    }
+}
+
+/// Saved versions of a note, newest first
+#[tauri::command]
+pub fn list_versions(path: String, state: State<AppState>) -> Result<Vec<NoteVersion>, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let full_path = paths::resolve_note(&notes_dir, &path)?;
+    let rel_path = paths::to_relative(&notes_dir, &full_path);
+
+    let mut versions = load_index(&notes_dir, &rel_path).versions;
+    versions.reverse();
+    Ok(versions)
+}
+
+#[tauri::command]
+pub fn read_version(path: String, id: String, state: State<AppState>) -> Result<String, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let full_path = paths::resolve_note(&notes_dir, &path)?;
+    read_note_version(&notes_dir, &full_path, &id)
+}
+
+/// Line diff from version `from` to version `to`, or to the current note when `to` is omitted
+#[tauri::command]
+pub fn diff_versions(
+    path: String,
+    from: String,
+    to: Option<String>,
+    state: State<AppState>,
+) -> Result<Vec<DiffChunk>, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let full_path = paths::resolve_note(&notes_dir, &path)?;
+
+    let old = read_note_version(&notes_dir, &full_path, &from)?;
+    let new = match to {
+        Some(id) => read_note_version(&notes_dir, &full_path, &id)?,
+        None => fs::read_to_string(&full_path).map_err(|e| e.to_string())?,
+    };
+
+    Ok(diff::line_diff(&old, &new))
+}
+
+/// Replace the note with an earlier version. The current content is kept as a
+/// version first, so a restore can itself be undone. Returns the note as
+/// restored, with the version token to save it under.
+#[tauri::command]
+pub fn restore_version(path: String, id: String, state: State<AppState>) -> Result<NoteContent, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let full_path = paths::resolve_note(&notes_dir, &path)?;
+
+    let content = read_note_version(&notes_dir, &full_path, &id)?;
+    snapshot_file(&notes_dir, &full_path, &content);
+
+    if let Some(parent) = full_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    atomic::write(&full_path, &content).map_err(|e| e.to_string())?;
+    state.index.lock().unwrap().update(&full_path);
+
+    println!("Restored {} to version {}", path, &id[..12]);
+    Ok(NoteContent {
+        path,
+        version: content_version(&content),
+        frontmatter: frontmatter::parse(&content).0,
+        content,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn versions_are_only_readable_through_their_own_note() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        let a = root.join("a.md");
+        let b = root.join("b.md");
+        snapshot(root, &a, "old a").unwrap();
+        snapshot(root, &b, "old b").unwrap();
+
+        assert_eq!(read_note_version(root, &a, &hash("old a")).unwrap(), "old a");
+        assert!(read_note_version(root, &a, &hash("old b")).is_err());
+        assert!(read_note_version(root, &b, &hash("old a")).is_err());
+        assert!(read_note_version(root, &a, "../../a.md").is_err());
+    }
+
+    fn versions(now: DateTime<Local>, ages: &[Duration]) -> Vec<NoteVersion> {
+        ages.iter()
+            .map(|age| NoteVersion {
+                id: String::new(),
+                saved_at: (now - *age).to_rfc3339(),
+                size_bytes: 0,
+            })
+            .collect()
+    }
+
+    fn noon() -> DateTime<Local> {
+        use chrono::TimeZone;
+        Local.with_ymd_and_hms(2026, 6, 10, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn thinning_keeps_the_last_hour() {
+        let now = noon();
+        let versions = versions(now, &[Duration::minutes(50), Duration::minutes(20), Duration::minutes(1)]);
+        assert_eq!(thin(&versions, now), [true, true, true]);
+    }
+
+    #[test]
+    fn thinning_keeps_the_newest_per_hour_and_day() {
+        let now = noon();
+        let versions = versions(
+            now,
+            &[
+                // Three days back, two on the same day
+                Duration::days(3) + Duration::minutes(30),
+                Duration::days(3),
+                Duration::days(2),
+                // Earlier today, two in the same hour
+                Duration::hours(3) + Duration::minutes(5),
+                Duration::hours(2) + Duration::minutes(50),
+                Duration::hours(2) + Duration::minutes(10),
+                Duration::minutes(10),
+            ],
+        );
+        assert_eq!(thin(&versions, now), [false, true, true, true, false, true, true]);
+    }
+
+    #[test]
+    fn thinning_keeps_versions_it_cannot_date() {
+        let now = noon();
+        let mut versions = versions(now, &[Duration::days(5), Duration::days(5)]);
+        versions[0].saved_at = "yesterday-ish".to_string();
+        assert_eq!(thin(&versions, now), [true, true]);
+    }
+}
@@ -0,0 +1,300 @@
+use crate::atomic;
+use crate::commands::audio::RECORDING_EXTENSIONS;
+use crate::commands::notes::{extract_preview, iso_timestamp, meta_dir, note_files, title_of, NoteMeta};
+use crate::frontmatter::{self, Frontmatter};
+use crate::markdown;
+use crate::paths;
+use crate::AppState;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::MutexGuard;
+use std::time::SystemTime;
+use tauri::State;
+
+// Note metadata cached in <notes_dir>/.onote/index.bin, keyed by relative path.
+// A note is only re-read when its mtime or size changes, so listing a folder or
+// the tags doesn't open every file. Saves and the watcher keep it current while
+// the app runs; opening the index catches up with changes made while it wasn't.
+
+// Bump when `IndexedNote` changes; an index in an older format is rebuilt
+const INDEX_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedNote {
+    pub modified: SystemTime,
+    pub created: Option<SystemTime>,
+    pub size: u64,
+    pub title: String,
+    pub preview: String,
+    pub word_count: usize,
+    pub tags: Vec<String>,
+    pub aliases: Vec<String>,
+    /// Wiki link targets as written
+    pub links: Vec<String>,
+    /// Linked to a recording, through frontmatter or by file name
+    pub has_audio: bool,
+    /// Unchecked `- [ ]` tasks
+    pub open_tasks: usize,
+    // Stored as JSON: bincode can't read back arbitrary YAML values
+    frontmatter: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexFile {
+    version: u32,
+    notes: HashMap<String, IndexedNote>,
+}
+
+#[derive(Default)]
+pub struct NoteIndex {
+    notes_dir: Option<PathBuf>,
+    notes: HashMap<String, IndexedNote>,
+    dirty: bool,
+}
+
+fn index_path(notes_dir: &Path) -> PathBuf {
+    meta_dir(notes_dir).join("index.bin")
+}
+
+fn is_note(path: &Path) -> bool {
+    path.extension().map(|ext| ext == "md" || ext == "txt").unwrap_or(false)
+}
+
+// Recordings are referenced by file name, e.g. `recording_20260101_120000.wav`
+fn mentions_recording(text: &str) -> bool {
+    text.split(|c: char| c.is_whitespace() || "()[]<>\"'`|".contains(c))
+        .filter_map(|word| Path::new(word.trim_end_matches(['.', ',', ';', ':'])).extension()?.to_str())
+        .any(|ext| RECORDING_EXTENSIONS.contains(&ext))
+}
+
+/// Index entry for the note at `path`, read from disk
+pub fn read_entry(path: &Path) -> Option<IndexedNote> {
+    let metadata = fs::metadata(path).ok()?;
+    let content = fs::read_to_string(path).ok()?;
+    let (frontmatter, body) = frontmatter::parse(&content);
+
+    Some(IndexedNote {
+        modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+        created: metadata.created().ok(),
+        size: metadata.len(),
+        title: title_of(frontmatter.as_ref(), body, path),
+        preview: extract_preview(body),
+        word_count: body.split_whitespace().count(),
+        tags: markdown::extract_tags(frontmatter.as_ref(), body),
+        aliases: frontmatter.as_ref().map(|f| f.aliases.clone()).unwrap_or_default(),
+        links: markdown::extract_links(body).into_iter().map(|l| l.target).collect(),
+        has_audio: frontmatter.as_ref().map(|f| f.source_recording.is_some()).unwrap_or(false)
+            || mentions_recording(body),
+        open_tasks: markdown::open_tasks(body),
+        frontmatter: frontmatter.and_then(|f| serde_json::to_string(&f).ok()),
+    })
+}
+
+impl IndexedNote {
+    pub fn frontmatter(&self) -> Option<Frontmatter> {
+        self.frontmatter.as_ref().and_then(|json| serde_json::from_str(json).ok())
+    }
+
+    pub fn to_meta(&self, rel_path: &str) -> NoteMeta {
+        NoteMeta {
+            id: rel_path.to_string(),
+            path: rel_path.to_string(),
+            title: self.title.clone(),
+            preview: self.preview.clone(),
+            modified: iso_timestamp(self.modified),
+            created: self.created.map(iso_timestamp),
+            word_count: self.word_count,
+            tags: self.tags.clone(),
+            frontmatter: self.frontmatter(),
+            pinned: false,
+        }
+    }
+}
+
+impl NoteIndex {
+    /// Load the index of `notes_dir` and bring it up to date with the files
+    pub fn open(&mut self, notes_dir: &Path) {
+        self.notes = fs::read(index_path(notes_dir))
+            .ok()
+            .and_then(|bytes| bincode::deserialize::<IndexFile>(&bytes).ok())
+            .filter(|file| file.version == INDEX_VERSION)
+            .map(|file| file.notes)
+            .unwrap_or_default();
+        self.notes_dir = Some(notes_dir.to_path_buf());
+        self.dirty = false;
+
+        self.refresh();
+    }
+
+    pub fn is_open(&self, notes_dir: &Path) -> bool {
+        self.notes_dir.as_deref() == Some(notes_dir)
+    }
+
+    // Re-read one note if it changed since it was indexed, or drop it if it's gone
+    fn check(&mut self, root: &Path, path: &Path) -> bool {
+        let rel = paths::to_relative(root, path);
+        let unchanged = match (self.notes.get(&rel), fs::metadata(path)) {
+            (Some(entry), Ok(metadata)) => {
+                metadata.modified().ok() == Some(entry.modified) && metadata.len() == entry.size
+            }
+            _ => false,
+        };
+        if unchanged {
+            return false;
+        }
+
+        match read_entry(path) {
+            Some(entry) => self.notes.insert(rel, entry),
+            None => self.notes.remove(&rel),
+        };
+        self.dirty = true;
+        true
+    }
+
+    // Check `files`, then drop indexed notes matching `scope` that weren't among them
+    fn sync(&mut self, root: &Path, files: &[PathBuf], scope: impl Fn(&Path) -> bool) -> usize {
+        let updated = files.iter().filter(|path| self.check(root, path)).count();
+
+        let present: HashSet<String> = files.iter().map(|p| paths::to_relative(root, p)).collect();
+        let before = self.notes.len();
+        self.notes
+            .retain(|rel, _| present.contains(rel) || !scope(Path::new(rel)));
+        if self.notes.len() != before {
+            self.dirty = true;
+        }
+
+        updated
+    }
+
+    /// Check every note in the vault
+    pub fn refresh(&mut self) {
+        let root = match self.notes_dir.clone() {
+            Some(root) => root,
+            None => return,
+        };
+
+        let updated = self.sync(&root, &note_files(&root), |_| true);
+        if updated > 0 {
+            println!("Indexed {} changed notes", updated);
+        }
+        self.flush();
+    }
+
+    /// Check a note, or every note under a folder, after it was written, created
+    /// or removed
+    pub fn update(&mut self, full_path: &Path) {
+        let root = match self.notes_dir.clone() {
+            Some(root) if full_path.starts_with(&root) => root,
+            _ => return,
+        };
+        let rel = PathBuf::from(paths::to_relative(&root, full_path));
+
+        if full_path.is_dir() {
+            self.sync(&root, &note_files(full_path), |p| p.starts_with(&rel));
+        } else if full_path.exists() {
+            // Hidden folders such as .trash aren't part of the vault
+            let hidden = rel.components().any(|c| c.as_os_str().to_string_lossy().starts_with('.'));
+            if is_note(full_path) && !hidden {
+                self.check(&root, full_path);
+            }
+        } else {
+            // Gone, and it may have been a folder
+            self.sync(&root, &[], |p| p.starts_with(&rel));
+        }
+
+        self.flush();
+    }
+
+    /// Notes directly inside `folder_path`, checking each of them first
+    pub fn folder(&mut self, folder_path: &Path) -> Vec<(String, &IndexedNote)> {
+        let root = match self.notes_dir.clone() {
+            Some(root) => root,
+            None => return Vec::new(),
+        };
+
+        let files: Vec<PathBuf> = fs::read_dir(folder_path)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.path())
+                    .filter(|p| p.is_file() && is_note(p))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let rel_folder = PathBuf::from(paths::to_relative(&root, folder_path));
+        self.sync(&root, &files, |p| p.parent() == Some(rel_folder.as_path()));
+        self.flush();
+
+        files
+            .iter()
+            .map(|f| paths::to_relative(&root, f))
+            .filter_map(|rel| self.notes.get(&rel).map(|entry| (rel, entry)))
+            .collect()
+    }
+
+    pub fn get(&self, rel_path: &str) -> Option<&IndexedNote> {
+        self.notes.get(rel_path)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &IndexedNote)> {
+        self.notes.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    /// Write the index file if anything changed since it was last written
+    pub fn flush(&mut self) {
+        let root = match (&self.notes_dir, self.dirty) {
+            (Some(root), true) => root.clone(),
+            _ => return,
+        };
+
+        let file = IndexFile {
+            version: INDEX_VERSION,
+            notes: std::mem::take(&mut self.notes),
+        };
+        let result = bincode::serialize(&file).map_err(|e| e.to_string()).and_then(|bytes| {
+            fs::create_dir_all(meta_dir(&root)).map_err(|e| e.to_string())?;
+            atomic::write(&index_path(&root), bytes).map_err(|e| e.to_string())
+        });
+        self.notes = file.notes;
+
+        match result {
+            Ok(()) => self.dirty = false,
+            Err(e) => eprintln!("Failed to write note index: {}", e),
+        }
+    }
+}
+
+/// The index of the current notes directory. Without a running watcher nobody
+/// reports outside edits, so the whole vault is checked first.
+pub fn lock_index(state: &AppState) -> MutexGuard<'_, NoteIndex> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let watching = state.watcher.lock().unwrap().is_some();
+
+    let mut index = state.index.lock().unwrap();
+    if !index.is_open(&notes_dir) {
+        index.open(&notes_dir);
+    } else if !watching {
+        index.refresh();
+    }
+    index
+}
+
+/// Throw the index away and build it again from the notes on disk
+#[tauri::command]
+pub fn rebuild_index(state: State<AppState>) -> Result<usize, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let mut index = state.index.lock().unwrap();
+
+    *index = NoteIndex::default();
+    fs::remove_file(index_path(&notes_dir)).ok();
+    index.open(&notes_dir);
+
+    Ok(index.len())
+}
@@ -0,0 +1,440 @@
+use crate::atomic;
+use crate::commands::history;
+use crate::commands::notes::{extract_title, note_files};
+use crate::frontmatter;
+use crate::markdown::{self, WikiLink};
+use crate::paths;
+use crate::AppState;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Backlink {
+    pub path: String,
+    pub title: String,
+    pub matches: Vec<BacklinkContext>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BacklinkContext {
+    pub line_number: usize,
+    pub line_content: String,
+}
+
+// What a link target can be matched against
+struct NoteRef {
+    path: PathBuf,
+    rel: String,
+    stem: String,
+    title: String,
+    aliases: Vec<String>,
+}
+
+// How a link found its note; decides how it is rewritten when the note moves
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MatchKind {
+    Path,
+    Stem,
+    Title,
+}
+
+fn rel_key(rel: &str) -> String {
+    rel.replace('\\', "/").to_lowercase()
+}
+
+fn strip_note_ext(rel: &str) -> &str {
+    rel.strip_suffix(".md")
+        .or_else(|| rel.strip_suffix(".txt"))
+        .unwrap_or(rel)
+}
+
+fn folder_of(rel: &str) -> &str {
+    rel.rsplit_once('/').map(|(folder, _)| folder).unwrap_or("")
+}
+
+struct Catalog {
+    notes: Vec<NoteRef>,
+}
+
+impl Catalog {
+    fn load(notes_dir: &Path) -> Self {
+        let notes = note_files(notes_dir)
+            .into_iter()
+            .map(|path| {
+                let content = fs::read_to_string(&path).unwrap_or_default();
+                let aliases = frontmatter::parse(&content)
+                    .0
+                    .map(|f| f.aliases)
+                    .unwrap_or_default();
+
+                NoteRef {
+                    rel: paths::to_relative(notes_dir, &path).replace('\\', "/"),
+                    stem: path
+                        .file_stem()
+                        .map(|s| s.to_string_lossy().to_lowercase())
+                        .unwrap_or_default(),
+                    title: extract_title(&content, &path).to_lowercase(),
+                    aliases: aliases.iter().map(|a| a.to_lowercase()).collect(),
+                    path,
+                }
+            })
+            .collect();
+
+        Catalog { notes }
+    }
+
+    /// Resolve a link target written in the note at `from` (vault-relative).
+    /// Paths win over file names, file names over titles and aliases; ties go
+    /// to the note closest to `from`, then to the shortest path.
+    fn resolve(&self, target: &str, from: Option<&str>) -> Option<(usize, MatchKind)> {
+        let key = rel_key(target.trim());
+        let key = strip_note_ext(&key);
+        let from_folder = from.map(|f| folder_of(f).to_lowercase()).unwrap_or_default();
+
+        let pick = |matches: Vec<usize>| -> Option<usize> {
+            matches.into_iter().min_by_key(|&i| {
+                let rel = &self.notes[i].rel;
+                let same_folder = folder_of(rel).to_lowercase() == from_folder;
+                (!same_folder, rel.len())
+            })
+        };
+
+        if key.contains('/') {
+            let from_folder_key = if from_folder.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}/{}", from_folder, key)
+            };
+            let found = self.notes.iter().position(|n| {
+                let rel = rel_key(&n.rel);
+                let rel = strip_note_ext(&rel);
+                rel == key || rel == from_folder_key
+            });
+            return found.map(|i| (i, MatchKind::Path));
+        }
+
+        let by_stem: Vec<usize> = (0..self.notes.len())
+            .filter(|&i| self.notes[i].stem == key)
+            .collect();
+        if let Some(i) = pick(by_stem) {
+            return Some((i, MatchKind::Stem));
+        }
+
+        let by_title: Vec<usize> = (0..self.notes.len())
+            .filter(|&i| {
+                let note = &self.notes[i];
+                note.title == key || note.aliases.iter().any(|a| a == key)
+            })
+            .collect();
+        pick(by_title).map(|i| (i, MatchKind::Title))
+    }
+}
+
+fn links_in(content: &str) -> (usize, Vec<WikiLink>) {
+    let body = frontmatter::parse(content).1;
+    // The body is a suffix of the content, so its offset is the length difference
+    (content.len() - body.len(), markdown::extract_links(body))
+}
+
+// Lines of `content`, the note `from`, that link to `target`. Line numbers count
+// from the start of the file, frontmatter included.
+fn contexts(catalog: &Catalog, from: &NoteRef, content: &str, target: &Path) -> Vec<BacklinkContext> {
+    let (offset, links) = links_in(content);
+    let body_line = content[..offset].lines().count();
+    let lines: Vec<&str> = content[offset..].lines().collect();
+
+    let mut matches: Vec<BacklinkContext> = Vec::new();
+    for link in links {
+        let points_here = catalog
+            .resolve(&link.target, Some(&from.rel))
+            .map(|(i, _)| catalog.notes[i].path == target)
+            .unwrap_or(false);
+
+        let line_number = body_line + link.line + 1;
+        if points_here && !matches.iter().any(|m| m.line_number == line_number) {
+            matches.push(BacklinkContext {
+                line_number,
+                line_content: lines.get(link.line).map(|l| l.trim().to_string()).unwrap_or_default(),
+            });
+        }
+    }
+    matches
+}
+
+#[tauri::command]
+pub fn get_backlinks(path: String, state: State<AppState>) -> Result<Vec<Backlink>, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let full_path = paths::resolve_note(&notes_dir, &path)?;
+    let catalog = Catalog::load(&notes_dir);
+
+    let mut backlinks = Vec::new();
+    for note in &catalog.notes {
+        if note.path == full_path {
+            continue;
+        }
+        let content = match fs::read_to_string(&note.path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+
+        let matches = contexts(&catalog, note, &content, &full_path);
+        if !matches.is_empty() {
+            backlinks.push(Backlink {
+                path: note.rel.clone(),
+                title: extract_title(&content, &note.path),
+                matches,
+            });
+        }
+    }
+
+    backlinks.sort_by_key(|b| b.title.to_lowercase());
+    Ok(backlinks)
+}
+
+/// Path of the note a link target points to, as seen from the note at `from`
+#[tauri::command]
+pub fn resolve_link(target: String, from: Option<String>, state: State<AppState>) -> Result<Option<String>, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let catalog = Catalog::load(&notes_dir);
+    let from = from.map(|f| f.replace('\\', "/"));
+
+    Ok(catalog
+        .resolve(&target, from.as_deref())
+        .map(|(i, _)| paths::to_relative(&notes_dir, &catalog.notes[i].path)))
+}
+
+// One link to rewrite: byte range in the file and its replacement target
+struct Edit {
+    range: std::ops::Range<usize>,
+    replacement: String,
+}
+
+/// Links that will break when notes move, collected before the move happens
+pub struct LinkUpdates {
+    edits: HashMap<PathBuf, Vec<Edit>>,
+}
+
+/// Find every link pointing at a note in `moves` (old path → new path, both
+/// absolute) and work out its new target. Call before moving, then `apply` after.
+pub fn plan_link_updates(notes_dir: &Path, moves: &HashMap<PathBuf, PathBuf>) -> LinkUpdates {
+    plan(notes_dir, moves, |_| true)
+}
+
+/// Links inside freshly made copies (original → copy, both absolute) that point
+/// at one of the originals by path, retargeted to its copy. Call after copying.
+pub fn plan_copy_links(notes_dir: &Path, copies: &HashMap<PathBuf, PathBuf>) -> LinkUpdates {
+    let made: HashSet<&PathBuf> = copies.values().collect();
+    plan(notes_dir, copies, |path| made.contains(&path.to_path_buf()))
+}
+
+// Links in notes accepted by `source` that point at a note in `moves`
+fn plan(notes_dir: &Path, moves: &HashMap<PathBuf, PathBuf>, source: impl Fn(&Path) -> bool) -> LinkUpdates {
+    let catalog = Catalog::load(notes_dir);
+    let mut edits: HashMap<PathBuf, Vec<Edit>> = HashMap::new();
+
+    for note in catalog.notes.iter().filter(|n| source(&n.path)) {
+        let content = match fs::read_to_string(&note.path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        let (offset, links) = links_in(&content);
+
+        for link in links {
+            let (i, kind) = match catalog.resolve(&link.target, Some(&note.rel)) {
+                Some(found) => found,
+                None => continue,
+            };
+            let target = &catalog.notes[i];
+            let new_path = match moves.get(&target.path) {
+                Some(new_path) => new_path,
+                None => continue,
+            };
+
+            let new_rel = paths::to_relative(notes_dir, new_path).replace('\\', "/");
+            let replacement = match kind {
+                MatchKind::Path => {
+                    // Keep the extension only if the link had one
+                    let has_ext = link.target.ends_with(".md") || link.target.ends_with(".txt");
+                    if has_ext {
+                        new_rel
+                    } else {
+                        strip_note_ext(&new_rel).to_string()
+                    }
+                }
+                MatchKind::Stem => {
+                    let new_stem = new_path
+                        .file_stem()
+                        .map(|s| s.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    if new_stem.to_lowercase() == target.stem {
+                        continue;
+                    }
+                    new_stem
+                }
+                // Titles and aliases don't change when the file moves
+                MatchKind::Title => continue,
+            };
+
+            edits.entry(note.path.clone()).or_default().push(Edit {
+                range: offset + link.target_range.start..offset + link.target_range.end,
+                replacement,
+            });
+        }
+    }
+
+    LinkUpdates { edits }
+}
+
+impl LinkUpdates {
+    /// Rewrite the planned links. Notes that moved themselves are found at their
+    /// new path. Returns the number of notes changed.
+    pub fn apply(self, notes_dir: &Path, moves: &HashMap<PathBuf, PathBuf>) -> usize {
+        let mut changed = 0;
+
+        for (path, mut edits) in self.edits {
+            let path = moves.get(&path).cloned().unwrap_or(path);
+            let mut content = match fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(_) => continue,
+            };
+            let original = content.clone();
+
+            // Back to front so earlier ranges stay valid
+            edits.sort_by_key(|e| std::cmp::Reverse(e.range.start));
+            for edit in edits {
+                if content.is_char_boundary(edit.range.start) && content.is_char_boundary(edit.range.end) {
+                    content.replace_range(edit.range, &edit.replacement);
+                }
+            }
+
+            if content != original {
+                history::snapshot_file(notes_dir, &path, &content);
+                match atomic::write(&path, &content) {
+                    Ok(()) => changed += 1,
+                    Err(e) => eprintln!("Failed to update links in {:?}: {}", path, e),
+                }
+            }
+        }
+
+        if changed > 0 {
+            println!("Updated links in {} notes", changed);
+        }
+        changed
+    }
+}
+
+/// Old → new paths of every note under `old_dir` once it is moved to `new_dir`
+pub fn folder_moves(notes_dir: &Path, old_dir: &Path, new_dir: &Path) -> HashMap<PathBuf, PathBuf> {
+    note_files(notes_dir)
+        .into_iter()
+        .filter_map(|path| {
+            let rest = path.strip_prefix(old_dir).ok()?.to_path_buf();
+            Some((path, new_dir.join(rest)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn vault(files: &[(&str, &str)]) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        for (rel, content) in files {
+            let path = dir.path().join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    fn resolved(catalog: &Catalog, target: &str, from: &str) -> Option<(String, MatchKind)> {
+        catalog
+            .resolve(target, Some(from))
+            .map(|(i, kind)| (catalog.notes[i].rel.clone(), kind))
+    }
+
+    #[test]
+    fn resolves_paths_then_file_names_then_titles_and_aliases() {
+        let dir = vault(&[
+            ("projects/alpha.md", "---\naliases: [First]\n---\n# Alpha Project\n"),
+            ("inbox/alpha.md", "# Inbox alpha\n"),
+            ("inbox/from.md", ""),
+            ("projects/from.md", ""),
+        ]);
+        let catalog = Catalog::load(dir.path());
+
+        let path = Some(("projects/alpha.md".to_string(), MatchKind::Path));
+        assert_eq!(resolved(&catalog, "projects/alpha", "inbox/from.md"), path);
+        assert_eq!(resolved(&catalog, "Projects/Alpha.md", "inbox/from.md"), path);
+
+        // A file name prefers the note in the linking note's folder
+        let stem = |rel: &str| Some((rel.to_string(), MatchKind::Stem));
+        assert_eq!(resolved(&catalog, "alpha", "inbox/from.md"), stem("inbox/alpha.md"));
+        assert_eq!(resolved(&catalog, "alpha", "projects/from.md"), stem("projects/alpha.md"));
+
+        let title = Some(("projects/alpha.md".to_string(), MatchKind::Title));
+        assert_eq!(resolved(&catalog, "alpha project", "inbox/from.md"), title);
+        assert_eq!(resolved(&catalog, "first", "inbox/from.md"), title);
+
+        assert_eq!(resolved(&catalog, "missing", "inbox/from.md"), None);
+    }
+
+    #[test]
+    fn rewrites_links_to_a_moved_note() {
+        let dir = vault(&[
+            ("a.md", "# A\n[[notes/b]] [[b|see b]] [[B Title#part]] [[notes/b.md]]\n"),
+            ("notes/b.md", "# B Title\n"),
+        ]);
+        let root = dir.path();
+        let moves = HashMap::from([(root.join("notes/b.md"), root.join("archive/c.md"))]);
+
+        let updates = plan_link_updates(root, &moves);
+        fs::create_dir_all(root.join("archive")).unwrap();
+        fs::rename(root.join("notes/b.md"), root.join("archive/c.md")).unwrap();
+        assert_eq!(updates.apply(root, &moves), 1);
+
+        assert_eq!(
+            fs::read_to_string(root.join("a.md")).unwrap(),
+            "# A\n[[archive/c]] [[c|see b]] [[B Title#part]] [[archive/c.md]]\n"
+        );
+    }
+
+    #[test]
+    fn rewrites_links_inside_a_moved_note() {
+        let dir = vault(&[("notes/a.md", "# A\n[[notes/b]]\n"), ("notes/b.md", "# B\n")]);
+        let root = dir.path();
+        let moves = HashMap::from([
+            (root.join("notes/a.md"), root.join("done/a.md")),
+            (root.join("notes/b.md"), root.join("done/b.md")),
+        ]);
+
+        let updates = plan_link_updates(root, &moves);
+        fs::rename(root.join("notes"), root.join("done")).unwrap();
+        updates.apply(root, &moves);
+
+        assert_eq!(fs::read_to_string(root.join("done/a.md")).unwrap(), "# A\n[[done/b]]\n");
+    }
+
+    #[test]
+    fn backlink_lines_count_from_the_start_of_the_file() {
+        let dir = vault(&[
+            ("from.md", "---\ntitle: From\ntags: [x]\n---\n# From\n\nSee [[to]] here\n"),
+            ("to.md", "# To\n"),
+        ]);
+        let root = dir.path();
+        let catalog = Catalog::load(root);
+        let from = catalog.notes.iter().find(|n| n.rel == "from.md").unwrap();
+        let content = fs::read_to_string(&from.path).unwrap();
+
+        let matches = contexts(&catalog, from, &content, &root.join("to.md"));
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].line_number, 7);
+        assert_eq!(matches[0].line_content, "See [[to]] here");
+        assert_eq!(content.lines().nth(6), Some("See [[to]] here"));
+    }
+}
@@ -1,4 +1,19 @@
 pub mod audio;
+pub mod batch;
+pub mod history;
+pub mod index;
+pub mod links;
 pub mod notes;
+pub mod order;
+pub mod quick_open;
+pub mod recovery;
+pub mod replace;
+pub mod search;
+pub mod semantic;
 pub mod settings;
+pub mod storage;
+pub mod tags;
+pub mod transcript_cache;
+pub mod trash;
+pub mod views;
 pub mod whisper;
@@ -1,7 +1,16 @@
+use crate::atomic;
+use crate::commands::index::lock_index;
+use crate::commands::settings::{load_settings, ConflictStrategy};
+use crate::commands::order::{self, FolderOrder};
+use crate::commands::{history, links, quick_open, trash};
+use crate::frontmatter::{self, Frontmatter};
+use crate::paths;
 use crate::AppState;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tauri::State;
 use walkdir::WalkDir;
 
@@ -18,31 +27,76 @@ pub struct NoteMeta {
     pub path: String,
     pub title: String,
     pub preview: String,
+    /// ISO-8601 in UTC, e.g. `2026-01-31T09:15:00Z`
     pub modified: String,
+    /// Same format; None where the file system doesn't record creation times
+    pub created: Option<String>,
     pub word_count: usize,
+    pub tags: Vec<String>,
+    pub frontmatter: Option<Frontmatter>,
+    /// Pinned to the top of its folder
+    pub pinned: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct NoteContent {
     pub path: String,
     pub content: String,
+    /// Token for the content as read; pass it back to `save_note` to detect external edits
+    pub version: String,
+    pub frontmatter: Option<Frontmatter>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct SearchResult {
+pub struct SaveResult {
+    /// Where the content ended up: the note itself, or its conflict copy
     pub path: String,
-    pub title: String,
-    pub matches: Vec<SearchMatch>,
+    pub version: String,
+    pub conflict_copy: bool,
 }
 
+// Save errors are structured so the editor can offer to merge a conflict
 #[derive(Debug, Serialize, Deserialize)]
-pub struct SearchMatch {
-    pub line_number: usize,
-    pub line_content: String,
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SaveError {
+    /// The file changed on disk since it was read
+    Conflict {
+        path: String,
+        disk_content: String,
+        disk_version: String,
+        attempted_content: String,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl From<String> for SaveError {
+    fn from(message: String) -> Self {
+        SaveError::Failed { message }
+    }
+}
+
+impl From<paths::PathError> for SaveError {
+    fn from(e: paths::PathError) -> Self {
+        SaveError::Failed { message: e.to_string() }
+    }
 }
 
-fn extract_title(content: &str, path: &Path) -> String {
-    for line in content.lines() {
+/// Title from the frontmatter, else the first `# ` heading, else the file name
+pub fn extract_title(content: &str, path: &Path) -> String {
+    let (frontmatter, body) = frontmatter::parse(content);
+    title_of(frontmatter.as_ref(), body, path)
+}
+
+pub fn title_of(frontmatter: Option<&Frontmatter>, body: &str, path: &Path) -> String {
+    if let Some(title) = frontmatter.and_then(|f| f.title.as_ref()) {
+        if !title.trim().is_empty() {
+            return title.trim().to_string();
+        }
+    }
+
+    for line in body.lines() {
         let trimmed = line.trim();
         if trimmed.starts_with("# ") {
             return trimmed[2..].to_string();
@@ -53,7 +107,7 @@ fn extract_title(content: &str, path: &Path) -> String {
         .unwrap_or_else(|| "Untitled".to_string())
 }
 
-fn extract_preview(content: &str) -> String {
+pub fn extract_preview(content: &str) -> String {
     let text: String = content
         .lines()
         .filter(|l| !l.starts_with('#') && !l.trim().is_empty())
@@ -61,20 +115,85 @@ fn extract_preview(content: &str) -> String {
         .collect::<Vec<_>>()
         .join(" ");
 
-    if text.len() > 100 {
-        format!("{}...", &text[..100])
-    } else {
-        text
+    // Cut on a char boundary, notes aren't all ASCII
+    match text.char_indices().nth(100) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text,
     }
 }
 
-fn format_date(time: std::time::SystemTime) -> String {
-    use chrono::{DateTime, Local};
-    let datetime: DateTime<Local> = time.into();
-    datetime.format("%b %d").to_string()
+/// ISO-8601 timestamp in UTC, to the second. These sort as strings.
+pub fn iso_timestamp(time: std::time::SystemTime) -> String {
+    use chrono::{DateTime, SecondsFormat, Utc};
+    let datetime: DateTime<Utc> = time.into();
+    datetime.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoteSort {
+    /// Newest first
+    #[default]
+    Modified,
+    /// Newest first; notes without a creation time go by modified
+    Created,
+    /// A to Z
+    Title,
+    /// Longest first
+    WordCount,
+    /// As arranged by dragging; notes never arranged follow by file name
+    Manual,
+}
+
+/// Sort a listing, pinned notes first, falling back to the path so equal keys
+/// keep a stable order. `order` is the folder's, for manual sorting.
+pub fn sort_notes(notes: &mut [NoteMeta], sort: NoteSort, order: &FolderOrder) {
+    match sort {
+        NoteSort::Modified => notes.sort_by(|a, b| b.modified.cmp(&a.modified).then_with(|| a.path.cmp(&b.path))),
+        NoteSort::Created => notes.sort_by(|a, b| {
+            let created = |n: &NoteMeta| n.created.clone().unwrap_or_else(|| n.modified.clone());
+            created(b).cmp(&created(a)).then_with(|| a.path.cmp(&b.path))
+        }),
+        NoteSort::Title => notes.sort_by(|a, b| {
+            a.title
+                .to_lowercase()
+                .cmp(&b.title.to_lowercase())
+                .then_with(|| a.path.cmp(&b.path))
+        }),
+        NoteSort::WordCount => notes.sort_by(|a, b| b.word_count.cmp(&a.word_count).then_with(|| a.path.cmp(&b.path))),
+        NoteSort::Manual => notes.sort_by(|a, b| {
+            let position = |n: &NoteMeta| {
+                let name = Path::new(&n.path).file_name().map(|s| s.to_string_lossy().to_string());
+                name.and_then(|name| order.position(&name)).unwrap_or(usize::MAX)
+            };
+            position(a).cmp(&position(b)).then_with(|| a.path.cmp(&b.path))
+        }),
+    }
+    // Stable, so pinned notes keep the chosen order among themselves
+    notes.sort_by_key(|n| !n.pinned);
+}
+
+fn is_hidden(entry: &walkdir::DirEntry) -> bool {
+    entry.depth() > 0 && entry.file_name().to_string_lossy().starts_with('.')
+}
+
+/// All note files in the vault, skipping hidden folders such as `.audio`
+pub fn note_files(notes_dir: &Path) -> Vec<PathBuf> {
+    WalkDir::new(notes_dir)
+        .into_iter()
+        .filter_entry(|e| !is_hidden(e))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| {
+            e.path().extension()
+                .map(|ext| ext == "md" || ext == "txt")
+                .unwrap_or(false)
+        })
+        .map(|e| e.into_path())
+        .collect()
 }
 
-fn slugify(text: &str) -> String {
+pub fn slugify(text: &str) -> String {
     text.to_lowercase()
         .chars()
         .map(|c| if c.is_alphanumeric() { c } else { '-' })
@@ -83,6 +202,185 @@ fn slugify(text: &str) -> String {
         .to_string()
 }
 
+/// `path` if it is free, else the first free `<stem>-1.<ext>`, `<stem>-2.<ext>`...
+pub fn unique_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut full_path = path.to_path_buf();
+    let mut counter = 1;
+    while full_path.exists() {
+        full_path = path.with_file_name(format!("{}-{}{}", stem, counter, ext));
+        counter += 1;
+    }
+
+    full_path
+}
+
+/// `YYYY-MM-DD-slug.md` in `folder_path`, with a counter appended on collision
+pub fn unique_note_path(folder_path: &Path, date_str: &str, slug: &str) -> PathBuf {
+    unique_path(&folder_path.join(format!("{}-{}.md", date_str, slug)))
+}
+
+// The `YYYY-MM-DD-` that `create_note` puts in front of file names, if present
+fn date_prefix(stem: &str) -> &str {
+    match stem.get(..11) {
+        Some(prefix)
+            if prefix.ends_with('-')
+                && chrono::NaiveDate::parse_from_str(&prefix[..10], "%Y-%m-%d").is_ok() =>
+        {
+            prefix
+        }
+        _ => "",
+    }
+}
+
+// Where a note belongs once its file name follows the first line of its body,
+// keeping any date prefix; None if the name already fits
+fn title_file_name(full_path: &Path, content: &str) -> Option<PathBuf> {
+    let body = frontmatter::parse(content).1;
+    let slug = slugify(body.lines().next()?.trim_start_matches('#'));
+    if slug.is_empty() {
+        return None;
+    }
+
+    let stem = full_path.file_stem()?.to_string_lossy().to_string();
+    let ext = full_path.extension()?.to_string_lossy().to_string();
+    let wanted = format!("{}{}", date_prefix(&stem), slug);
+
+    // `wanted-2` is where a collision put it earlier
+    let suffix = stem.strip_prefix(&wanted);
+    let fits = suffix == Some("")
+        || suffix
+            .and_then(|rest| rest.strip_prefix('-'))
+            .map(|n| n.parse::<u32>().is_ok())
+            .unwrap_or(false);
+    if fits {
+        return None;
+    }
+
+    Some(unique_path(&full_path.with_file_name(format!("{}.{}", wanted, ext))))
+}
+
+// Whether two existing paths name the same file, e.g. differing only in case
+// on a case-insensitive file system
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (fs::metadata(a), fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Where the note at `old` goes when renamed to `wanted`: there if it's free or
+/// is `old` itself under another case, else with a counter appended, so a
+/// different note is never overwritten
+fn rename_target(old: &Path, wanted: &Path) -> PathBuf {
+    if !wanted.exists() || is_same_file(old, wanted) {
+        wanted.to_path_buf()
+    } else {
+        unique_path(wanted)
+    }
+}
+
+/// Move a note or folder from `old` to `new` along with everything that knows
+/// it by path: links to it, its open and version history, and the index
+pub fn relocate(state: &AppState, notes_dir: &Path, old: &Path, new: &Path) -> Result<(), String> {
+    relocate_all(state, notes_dir, &[(old.to_path_buf(), new.to_path_buf())])
+}
+
+/// `relocate` for several notes, files or folders at once (old → new). Missing
+/// parent folders are created. If one fails, the ones already moved go back.
+pub fn relocate_all(state: &AppState, notes_dir: &Path, renames: &[(PathBuf, PathBuf)]) -> Result<(), String> {
+    // Links have to be found before the notes move
+    let mut moves = HashMap::new();
+    for (old, new) in renames {
+        if old.is_dir() {
+            moves.extend(links::folder_moves(notes_dir, old, new));
+        } else {
+            moves.insert(old.clone(), new.clone());
+        }
+    }
+    let link_updates = links::plan_link_updates(notes_dir, &moves);
+
+    for (i, (old, new)) in renames.iter().enumerate() {
+        let result = match new.parent() {
+            Some(parent) => fs::create_dir_all(parent).and_then(|_| fs::rename(old, new)),
+            None => fs::rename(old, new),
+        };
+        if let Err(e) = result {
+            for (old, new) in renames[..i].iter().rev() {
+                fs::rename(new, old).ok();
+            }
+            return Err(e.to_string());
+        }
+    }
+
+    link_updates.apply(notes_dir, &moves);
+    quick_open::move_entries(notes_dir, &moves);
+    history::move_entries(notes_dir, &moves);
+    order::move_entries(notes_dir, renames);
+
+    let mut index = state.index.lock().unwrap();
+    for (old, new) in renames {
+        index.update(old);
+        index.update(new);
+    }
+    Ok(())
+}
+
+/// Content hash used as the version token of a note
+pub fn content_version(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+/// A free path next to `original` with a label, e.g. `note (conflict).md`,
+/// then `note (conflict 2).md` and so on
+pub fn labeled_sibling(original: &Path, label: &str) -> PathBuf {
+    let parent = original.parent().unwrap_or(Path::new(""));
+    let stem = original
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let ext = original
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut counter = 1;
+    loop {
+        let suffix = if counter == 1 {
+            format!(" ({})", label)
+        } else {
+            format!(" ({} {})", label, counter)
+        };
+        let candidate = parent.join(format!("{}{}{}", stem, suffix, ext));
+        if !candidate.exists() {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+/// Hidden folder inside the vault for app metadata (caches, indexes)
+pub fn meta_dir(notes_dir: &Path) -> PathBuf {
+    notes_dir.join(".onote")
+}
+
 #[tauri::command]
 pub fn list_folders(state: State<AppState>) -> Result<Vec<FolderInfo>, String> {
     let notes_dir = state.notes_dir.lock().unwrap().clone();
@@ -111,48 +409,24 @@ pub fn list_folders(state: State<AppState>) -> Result<Vec<FolderInfo>, String> {
     Ok(build_tree(&notes_dir, &notes_dir))
 }
 
+/// Notes directly in `folder`. `list_view` pages through a folder with its subfolders.
 #[tauri::command]
-pub fn list_notes(folder: String, state: State<AppState>) -> Result<Vec<NoteMeta>, String> {
+pub fn list_notes(folder: String, sort: Option<NoteSort>, state: State<AppState>) -> Result<Vec<NoteMeta>, String> {
     let notes_dir = state.notes_dir.lock().unwrap().clone();
-    let folder_path = notes_dir.join(&folder);
+    let folder_path = paths::resolve(&notes_dir, &folder)?;
 
     if !folder_path.exists() {
         return Ok(Vec::new());
     }
 
-    let mut notes = Vec::new();
-
-    if let Ok(entries) = fs::read_dir(&folder_path) {
-        for entry in entries.filter_map(|e| e.ok()) {
-            let path = entry.path();
-            if path.is_file() {
-                let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
-                if ext == "md" || ext == "txt" {
-                    if let Ok(content) = fs::read_to_string(&path) {
-                        let metadata = fs::metadata(&path).ok();
-                        let modified = metadata
-                            .and_then(|m| m.modified().ok())
-                            .map(format_date)
-                            .unwrap_or_else(|| "Unknown".to_string());
-
-                        let rel_path = path.strip_prefix(&notes_dir).unwrap_or(&path);
-
-                        notes.push(NoteMeta {
-                            id: rel_path.to_string_lossy().to_string(),
-                            path: rel_path.to_string_lossy().to_string(),
-                            title: extract_title(&content, &path),
-                            preview: extract_preview(&content),
-                            modified,
-                            word_count: content.split_whitespace().count(),
-                        });
-                    }
-                }
-            }
-        }
-    }
+    let mut notes: Vec<NoteMeta> = lock_index(&state)
+        .folder(&folder_path)
+        .into_iter()
+        .map(|(rel, note)| note.to_meta(&rel))
+        .collect();
 
-    // Sort by modified date (newest first) - for now by name
-    notes.sort_by(|a, b| b.modified.cmp(&a.modified));
+    order::mark_pinned(&notes_dir, &mut notes);
+    sort_notes(&mut notes, sort.unwrap_or_default(), &order::folder_order(&notes_dir, &folder_path));
 
     Ok(notes)
 }
@@ -160,30 +434,101 @@ pub fn list_notes(folder: String, state: State<AppState>) -> Result<Vec<NoteMeta
 #[tauri::command]
 pub fn read_note(path: String, state: State<AppState>) -> Result<NoteContent, String> {
     let notes_dir = state.notes_dir.lock().unwrap().clone();
-    let full_path = notes_dir.join(&path);
+    let full_path = paths::resolve_note(&notes_dir, &path)?;
 
     let content = fs::read_to_string(&full_path).map_err(|e| e.to_string())?;
-
-    Ok(NoteContent { path, content })
+    let version = content_version(&content);
+    let frontmatter = frontmatter::parse(&content).0;
+
+    Ok(NoteContent {
+        path,
+        content,
+        version,
+        frontmatter,
+    })
 }
 
+/// Save a note. With `expected_version` set, a file that changed on disk since it
+/// was read is not overwritten: depending on the conflict setting the save fails
+/// with both versions, or the content goes to a `(conflict)` copy next to it.
+/// `frontmatter`, when given, replaces whatever frontmatter `content` starts with.
 #[tauri::command]
-pub fn save_note(path: String, content: String, state: State<AppState>) -> Result<(), String> {
+pub fn save_note(
+    path: String,
+    content: String,
+    expected_version: Option<String>,
+    frontmatter: Option<Frontmatter>,
+    state: State<AppState>,
+) -> Result<SaveResult, SaveError> {
     let notes_dir = state.notes_dir.lock().unwrap().clone();
-    let full_path = notes_dir.join(&path);
+    let full_path = paths::resolve_note(&notes_dir, &path)?;
+
+    let content = match frontmatter {
+        Some(frontmatter) => frontmatter::render(&frontmatter, &content, frontmatter::parse(&content).1)?,
+        None => content,
+    };
 
     // Ensure parent directory exists
     if let Some(parent) = full_path.parent() {
         fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
 
-    fs::write(&full_path, content).map_err(|e| e.to_string())
+    let version = content_version(&content);
+
+    // A note deleted on disk is simply written again
+    if let (Some(expected), Ok(disk_content)) = (&expected_version, fs::read_to_string(&full_path)) {
+        let disk_version = content_version(&disk_content);
+        if &disk_version != expected && disk_version != version {
+            match load_settings().conflict_strategy {
+                ConflictStrategy::Refuse => {
+                    return Err(SaveError::Conflict {
+                        path,
+                        disk_content,
+                        disk_version,
+                        attempted_content: content,
+                    });
+                }
+                ConflictStrategy::ConflictCopy => {
+                    let copy_path = labeled_sibling(&full_path, "conflict");
+                    atomic::write(&copy_path, &content).map_err(|e| e.to_string())?;
+                    state.index.lock().unwrap().update(&copy_path);
+                    println!("Save conflict on {}, wrote {:?}", path, copy_path);
+
+                    return Ok(SaveResult {
+                        path: paths::to_relative(&notes_dir, &copy_path),
+                        version,
+                        conflict_copy: true,
+                    });
+                }
+            }
+        }
+    }
+
+    history::snapshot_file(&notes_dir, &full_path, &content);
+
+    atomic::write(&full_path, &content).map_err(|e| e.to_string())?;
+    state.index.lock().unwrap().update(&full_path);
+
+    let mut path = path;
+    if load_settings().sync_filename_to_title {
+        if let Some(new_path) = title_file_name(&full_path, &content) {
+            relocate(&state, &notes_dir, &full_path, &new_path)?;
+            path = paths::to_relative(&notes_dir, &new_path);
+            println!("Renamed note to follow its title: {}", path);
+        }
+    }
+
+    Ok(SaveResult {
+        path,
+        version,
+        conflict_copy: false,
+    })
 }
 
 #[tauri::command]
 pub fn create_note(folder: String, title: Option<String>, state: State<AppState>) -> Result<String, String> {
     let notes_dir = state.notes_dir.lock().unwrap().clone();
-    let folder_path = notes_dir.join(&folder);
+    let folder_path = paths::resolve(&notes_dir, &folder)?;
 
     // Ensure folder exists
     fs::create_dir_all(&folder_path).map_err(|e| e.to_string())?;
@@ -195,122 +540,344 @@ pub fn create_note(folder: String, title: Option<String>, state: State<AppState>
         .map(|t| slugify(t))
         .unwrap_or_else(|| "untitled".to_string());
 
-    let filename = format!("{}-{}.md", date_str, slug);
-    let mut full_path = folder_path.join(&filename);
-
-    // Handle duplicates
-    let mut counter = 1;
-    while full_path.exists() {
-        let new_filename = format!("{}-{}-{}.md", date_str, slug, counter);
-        full_path = folder_path.join(&new_filename);
-        counter += 1;
-    }
+    let full_path = unique_note_path(&folder_path, &date_str, &slug);
 
     // Create with initial content
     let initial_content = title
         .map(|t| format!("# {}\n\n", t))
         .unwrap_or_else(|| "# Untitled\n\n".to_string());
 
-    fs::write(&full_path, initial_content).map_err(|e| e.to_string())?;
+    atomic::write(&full_path, initial_content).map_err(|e| e.to_string())?;
+    state.index.lock().unwrap().update(&full_path);
 
-    let rel_path = full_path.strip_prefix(&notes_dir).unwrap_or(&full_path);
-    Ok(rel_path.to_string_lossy().to_string())
+    Ok(paths::to_relative(&notes_dir, &full_path))
 }
 
 #[tauri::command]
 pub fn delete_note(path: String, state: State<AppState>) -> Result<(), String> {
     let notes_dir = state.notes_dir.lock().unwrap().clone();
-    let full_path = notes_dir.join(&path);
+    let full_path = paths::resolve_note(&notes_dir, &path)?;
 
-    fs::remove_file(full_path).map_err(|e| e.to_string())
+    trash::move_to_trash(&notes_dir, &full_path)?;
+    order::remove_entries(&notes_dir, &full_path);
+    state.index.lock().unwrap().update(&full_path);
+    Ok(())
 }
 
 #[tauri::command]
 pub fn create_folder(name: String, parent: Option<String>, state: State<AppState>) -> Result<String, String> {
     let notes_dir = state.notes_dir.lock().unwrap().clone();
 
-    let folder_path = if let Some(parent_path) = parent {
-        notes_dir.join(&parent_path).join(&name)
-    } else {
-        notes_dir.join(&name)
-    };
+    let name = paths::validate_name(&name)?;
+    let parent_path = paths::resolve(&notes_dir, parent.as_deref().unwrap_or(""))?;
+    let folder_path = parent_path.join(name);
 
     fs::create_dir_all(&folder_path).map_err(|e| e.to_string())?;
 
-    let rel_path = folder_path.strip_prefix(&notes_dir).unwrap_or(&folder_path);
-    Ok(rel_path.to_string_lossy().to_string())
+    Ok(paths::to_relative(&notes_dir, &folder_path))
 }
 
 #[tauri::command]
 pub fn delete_folder(path: String, state: State<AppState>) -> Result<(), String> {
     let notes_dir = state.notes_dir.lock().unwrap().clone();
-    let full_path = notes_dir.join(&path);
+    let full_path = paths::resolve_entry(&notes_dir, &path)?;
 
-    // Recursively delete folder and all contents
-    fs::remove_dir_all(&full_path).map_err(|e| e.to_string())
+    if !full_path.is_dir() {
+        return Err(format!("Not a folder: {}", path));
+    }
+
+    // The folder and all its contents go to the trash as one item
+    trash::move_to_trash(&notes_dir, &full_path)?;
+    order::remove_entries(&notes_dir, &full_path);
+    state.index.lock().unwrap().update(&full_path);
+    Ok(())
 }
 
 #[tauri::command]
 pub fn rename_folder(old_path: String, new_name: String, state: State<AppState>) -> Result<String, String> {
     let notes_dir = state.notes_dir.lock().unwrap().clone();
-    let old_full_path = notes_dir.join(&old_path);
+    let old_full_path = paths::resolve_entry(&notes_dir, &old_path)?;
+    let new_name = paths::validate_name(&new_name)?;
 
     // Get parent directory
     let parent = old_full_path.parent()
         .ok_or_else(|| "Invalid folder path".to_string())?;
 
-    let new_full_path = parent.join(&new_name);
+    let new_full_path = parent.join(new_name);
 
     // Check if target already exists
     if new_full_path.exists() {
         return Err(format!("A folder named '{}' already exists", new_name));
     }
 
-    fs::rename(&old_full_path, &new_full_path).map_err(|e| e.to_string())?;
+    relocate(&state, &notes_dir, &old_full_path, &new_full_path)?;
+
+    Ok(paths::to_relative(&notes_dir, &new_full_path))
+}
+
+/// Move a folder, with everything in it, into `new_parent` ("" for the top
+/// level). Returns the new path.
+#[tauri::command]
+pub fn move_folder(path: String, new_parent: String, state: State<AppState>) -> Result<String, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let old_full_path = paths::resolve_entry(&notes_dir, &path)?;
+    let parent_path = paths::resolve(&notes_dir, &new_parent)?;
+
+    if !old_full_path.is_dir() {
+        return Err(format!("Not a folder: {}", path));
+    }
+    if !parent_path.is_dir() {
+        return Err(format!("Not a folder: {}", new_parent));
+    }
+    if parent_path.starts_with(&old_full_path) {
+        return Err("Can't move a folder into itself".to_string());
+    }
+
+    let name = old_full_path.file_name().ok_or("Invalid folder path")?;
+    let new_full_path = parent_path.join(name);
+    if new_full_path == old_full_path {
+        return Ok(path);
+    }
+    if new_full_path.exists() {
+        return Err(format!(
+            "A folder named '{}' already exists there; merge them instead",
+            name.to_string_lossy()
+        ));
+    }
+
+    relocate(&state, &notes_dir, &old_full_path, &new_full_path)?;
 
-    let rel_path = new_full_path.strip_prefix(&notes_dir).unwrap_or(&new_full_path);
-    Ok(rel_path.to_string_lossy().to_string())
+    Ok(paths::to_relative(&notes_dir, &new_full_path))
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MergeResult {
+    pub path: String,
+    pub moved: usize,
+    /// New paths of files renamed because the name was taken in the target
+    pub renamed: Vec<String>,
+}
+
+/// Move everything in `source` into `target`, keeping the layout of
+/// subfolders, then remove `source`. Files whose name is taken get a counter
+/// appended.
 #[tauri::command]
-pub fn search_notes(query: String, state: State<AppState>) -> Result<Vec<SearchResult>, String> {
+pub fn merge_folders(source: String, target: String, state: State<AppState>) -> Result<MergeResult, String> {
     let notes_dir = state.notes_dir.lock().unwrap().clone();
-    let query_lower = query.to_lowercase();
+    let source_path = paths::resolve_entry(&notes_dir, &source)?;
+    let target_path = paths::resolve(&notes_dir, &target)?;
 
-    let mut results = Vec::new();
+    if !source_path.is_dir() {
+        return Err(format!("Not a folder: {}", source));
+    }
+    if !target_path.is_dir() {
+        return Err(format!("Not a folder: {}", target));
+    }
+    if target_path.starts_with(&source_path) {
+        return Err("Can't merge a folder into itself".to_string());
+    }
 
-    for entry in WalkDir::new(&notes_dir)
+    let mut renames: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let mut renamed = Vec::new();
+    let mut planned = HashSet::new();
+    for entry in WalkDir::new(&source_path)
+        .min_depth(1)
         .into_iter()
         .filter_map(|e| e.ok())
-        .filter(|e| {
-            e.path().extension()
-                .map(|ext| ext == "md" || ext == "txt")
-                .unwrap_or(false)
-        })
+        .filter(|e| !e.file_type().is_dir())
     {
-        let path = entry.path();
-        if let Ok(content) = fs::read_to_string(path) {
-            let mut matches = Vec::new();
-
-            for (line_num, line) in content.lines().enumerate() {
-                if line.to_lowercase().contains(&query_lower) {
-                    matches.push(SearchMatch {
-                        line_number: line_num + 1,
-                        line_content: line.trim().to_string(),
-                    });
-                }
-            }
+        let rel = match entry.path().strip_prefix(&source_path) {
+            Ok(rel) => rel,
+            Err(_) => continue,
+        };
+        let wanted = target_path.join(rel);
+        let mut new_path = wanted.clone();
+        let mut counter = 1;
+        while new_path.exists() || planned.contains(&new_path) {
+            let stem = wanted.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            let name = match wanted.extension() {
+                Some(ext) => format!("{}-{}.{}", stem, counter, ext.to_string_lossy()),
+                None => format!("{}-{}", stem, counter),
+            };
+            new_path = wanted.with_file_name(name);
+            counter += 1;
+        }
+        if new_path != wanted {
+            renamed.push(paths::to_relative(&notes_dir, &new_path));
+        }
+        planned.insert(new_path.clone());
+        renames.push((entry.path().to_path_buf(), new_path));
+    }
 
-            if !matches.is_empty() {
-                let rel_path = path.strip_prefix(&notes_dir).unwrap_or(path);
-                results.push(SearchResult {
-                    path: rel_path.to_string_lossy().to_string(),
-                    title: extract_title(&content, path),
-                    matches,
-                });
-            }
+    relocate_all(&state, &notes_dir, &renames)?;
+
+    // Only empty folders are left; keep the source if anything else remains
+    let leftover = WalkDir::new(&source_path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .any(|e| !e.file_type().is_dir());
+    if !leftover {
+        fs::remove_dir_all(&source_path).map_err(|e| e.to_string())?;
+        order::remove_entries(&notes_dir, &source_path);
+    }
+
+    println!("Merged {:?} into {:?}: {} files", source_path, target_path, renames.len());
+    Ok(MergeResult {
+        path: paths::to_relative(&notes_dir, &target_path),
+        moved: renames.len(),
+        renamed,
+    })
+}
+
+/// Copy a folder and everything in it next to the original, as
+/// `name (copy)`. Links between notes inside it point at the copies.
+/// Returns the new path.
+#[tauri::command]
+pub fn duplicate_folder(path: String, state: State<AppState>) -> Result<String, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let old_full_path = paths::resolve_entry(&notes_dir, &path)?;
+
+    if !old_full_path.is_dir() {
+        return Err(format!("Not a folder: {}", path));
+    }
+
+    let new_full_path = labeled_sibling(&old_full_path, "copy");
+    let mut copies = HashMap::new();
+    // Symlinks are skipped so nothing from outside the vault is copied in.
+    // Recordings live in the vault-wide .audio folder and are referenced by
+    // file name, so the copies share them with the originals.
+    for entry in WalkDir::new(&old_full_path).into_iter().filter_map(|e| e.ok()) {
+        if entry.path_is_symlink() {
+            continue;
+        }
+        let rel = match entry.path().strip_prefix(&old_full_path) {
+            Ok(rel) => rel,
+            Err(_) => continue,
+        };
+        let dest = new_full_path.join(rel);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&dest).map_err(|e| e.to_string())?;
+        } else {
+            fs::copy(entry.path(), &dest).map_err(|e| e.to_string())?;
+            copies.insert(entry.path().to_path_buf(), dest);
         }
     }
 
-    Ok(results)
+    links::plan_copy_links(&notes_dir, &copies).apply(&notes_dir, &HashMap::new());
+    state.index.lock().unwrap().update(&new_full_path);
+
+    Ok(paths::to_relative(&notes_dir, &new_full_path))
+}
+
+/// Move a note into another folder. It keeps its file name unless that is
+/// taken there, in which case a counter is appended. Returns the new path.
+#[tauri::command]
+pub fn move_note(path: String, folder: String, state: State<AppState>) -> Result<String, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let old_full_path = paths::resolve_note(&notes_dir, &path)?;
+    let folder_path = paths::resolve(&notes_dir, &folder)?;
+
+    if !old_full_path.is_file() {
+        return Err(format!("Note not found: {}", path));
+    }
+    if !folder_path.is_dir() {
+        return Err(format!("Not a folder: {}", folder));
+    }
+    if old_full_path.parent() == Some(folder_path.as_path()) {
+        return Ok(paths::to_relative(&notes_dir, &old_full_path));
+    }
+
+    let file_name = old_full_path.file_name().ok_or("Invalid note path")?;
+    let new_full_path = unique_path(&folder_path.join(file_name));
+    relocate(&state, &notes_dir, &old_full_path, &new_full_path)?;
+
+    Ok(paths::to_relative(&notes_dir, &new_full_path))
+}
+
+/// Rename a note's file within its folder; `.md` is added unless `new_name`
+/// has a note extension. A counter is appended if the name is taken. Returns
+/// the new path.
+#[tauri::command]
+pub fn rename_note(path: String, new_name: String, state: State<AppState>) -> Result<String, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let old_full_path = paths::resolve_note(&notes_dir, &path)?;
+    let new_name = paths::validate_name(&new_name)?;
+
+    if !old_full_path.is_file() {
+        return Err(format!("Note not found: {}", path));
+    }
+
+    let has_ext = new_name.ends_with(".md") || new_name.ends_with(".txt");
+    let file_name = if has_ext {
+        new_name.to_string()
+    } else {
+        format!("{}.md", new_name)
+    };
+    let new_full_path = old_full_path.with_file_name(file_name);
+    if new_full_path == old_full_path {
+        return Ok(path);
+    }
+
+    let new_full_path = rename_target(&old_full_path, &new_full_path);
+    relocate(&state, &notes_dir, &old_full_path, &new_full_path)?;
+
+    Ok(paths::to_relative(&notes_dir, &new_full_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn rename_target_keeps_a_free_name() {
+        let dir = TempDir::new().unwrap();
+        let old = dir.path().join("foo.md");
+        fs::write(&old, "# Foo").unwrap();
+
+        assert_eq!(rename_target(&old, &dir.path().join("bar.md")), dir.path().join("bar.md"));
+    }
+
+    #[test]
+    fn rename_target_never_overwrites_another_note() {
+        let dir = TempDir::new().unwrap();
+        let old = dir.path().join("foo.md");
+        fs::write(&old, "# foo").unwrap();
+        fs::write(dir.path().join("bar.md"), "# bar").unwrap();
+
+        assert_eq!(rename_target(&old, &dir.path().join("bar.md")), dir.path().join("bar-1.md"));
+
+        // Names differing only in case are separate notes on case-sensitive file
+        // systems; on the others, `Foo.md` is `foo.md` itself
+        let upper = dir.path().join("Foo.md");
+        if fs::write(&upper, "# Foo").is_ok() && fs::read_to_string(&old).unwrap() == "# foo" {
+            assert_eq!(rename_target(&old, &upper), dir.path().join("Foo-1.md"));
+        } else {
+            assert_eq!(rename_target(&old, &upper), upper);
+        }
+    }
+
+    #[test]
+    fn rename_target_allows_a_case_only_rename() {
+        let dir = TempDir::new().unwrap();
+        let old = dir.path().join("foo.md");
+        fs::write(&old, "# Foo").unwrap();
+
+        assert_eq!(rename_target(&old, &dir.path().join("Foo.md")), dir.path().join("Foo.md"));
+    }
+
+    #[test]
+    fn preview_cuts_non_ascii_text_on_a_char_boundary() {
+        let cyrillic = "Привет мир ".repeat(20);
+        let preview = extract_preview(&cyrillic);
+        assert_eq!(preview, format!("{}...", cyrillic.chars().take(100).collect::<String>()));
+
+        // An emoji straddling byte 100
+        let emoji = format!("{}🎙️ recorded", "a".repeat(98));
+        let preview = extract_preview(&emoji);
+        assert!(preview.starts_with(&"a".repeat(98)));
+        assert_eq!(preview.chars().count(), 103);
+
+        assert_eq!(extract_preview("# Title\n\nShort note"), "Short note");
+    }
 }
@@ -0,0 +1,320 @@
+use crate::atomic;
+use crate::commands::notes::{meta_dir, NoteMeta};
+use crate::paths;
+use crate::AppState;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+// Pinned notes and the hand-arranged order of notes within each folder, kept in
+// <notes_dir>/.onote/order.json keyed by folder path. Notes are listed there by
+// file name; notes missing from a folder's order come after the ones in it.
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FolderOrder {
+    /// File names of notes shown at the top of the folder
+    pub pinned: Vec<String>,
+    /// File names in the order they were arranged
+    pub order: Vec<String>,
+}
+
+impl FolderOrder {
+    pub fn is_pinned(&self, name: &str) -> bool {
+        self.pinned.iter().any(|n| n == name)
+    }
+
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.order.iter().position(|n| n == name)
+    }
+
+    fn remove(&mut self, name: &str) {
+        self.pinned.retain(|n| n != name);
+        self.order.retain(|n| n != name);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pinned.is_empty() && self.order.is_empty()
+    }
+}
+
+fn order_path(notes_dir: &Path) -> PathBuf {
+    meta_dir(notes_dir).join("order.json")
+}
+
+fn load_order(notes_dir: &Path) -> HashMap<String, FolderOrder> {
+    fs::read_to_string(order_path(notes_dir))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_order(notes_dir: &Path, orders: &HashMap<String, FolderOrder>) -> Result<(), String> {
+    fs::create_dir_all(meta_dir(notes_dir)).map_err(|e| e.to_string())?;
+    let content = serde_json::to_string_pretty(orders).map_err(|e| e.to_string())?;
+    atomic::write(&order_path(notes_dir), content).map_err(|e| e.to_string())
+}
+
+// Folder key and file name of a note
+fn split(notes_dir: &Path, path: &Path) -> Option<(String, String)> {
+    let folder = paths::to_relative(notes_dir, path.parent()?);
+    let name = path.file_name()?.to_string_lossy().to_string();
+    Some((folder, name))
+}
+
+/// Order and pins of the notes in `folder`
+pub fn folder_order(notes_dir: &Path, folder: &Path) -> FolderOrder {
+    load_order(notes_dir)
+        .remove(&paths::to_relative(notes_dir, folder))
+        .unwrap_or_default()
+}
+
+/// Set `pinned` on each listed note from its own folder's pins
+pub fn mark_pinned(notes_dir: &Path, notes: &mut [NoteMeta]) {
+    let orders = load_order(notes_dir);
+    for note in notes {
+        let pinned = split(notes_dir, &notes_dir.join(&note.path))
+            .and_then(|(folder, name)| Some(orders.get(&folder)?.is_pinned(&name)));
+        note.pinned = pinned.unwrap_or(false);
+    }
+}
+
+/// Carry pins and positions over to the new paths of moved notes and folders
+/// (absolute old → new, after the move). A note renamed in place keeps its
+/// position; one moved to another folder keeps only its pin.
+pub fn move_entries(notes_dir: &Path, renames: &[(PathBuf, PathBuf)]) {
+    let mut orders = load_order(notes_dir);
+    let mut changed = false;
+
+    for (old, new) in renames {
+        if new.is_dir() {
+            let old_rel = PathBuf::from(paths::to_relative(notes_dir, old));
+            let new_rel = PathBuf::from(paths::to_relative(notes_dir, new));
+            let keys: Vec<String> = orders
+                .keys()
+                .filter(|key| Path::new(key).starts_with(&old_rel))
+                .cloned()
+                .collect();
+            for key in keys {
+                let new_key = match Path::new(&key).strip_prefix(&old_rel) {
+                    Ok(rest) if !rest.as_os_str().is_empty() => new_rel.join(rest),
+                    _ => new_rel.clone(),
+                };
+                if let Some(entry) = orders.remove(&key) {
+                    orders.insert(new_key.to_string_lossy().to_string(), entry);
+                    changed = true;
+                }
+            }
+            continue;
+        }
+
+        let ((old_folder, old_name), (new_folder, new_name)) = match (split(notes_dir, old), split(notes_dir, new)) {
+            (Some(old), Some(new)) => (old, new),
+            _ => continue,
+        };
+        let entry = match orders.get_mut(&old_folder) {
+            Some(entry) => entry,
+            None => continue,
+        };
+        let pinned = entry.is_pinned(&old_name);
+
+        if old_folder == new_folder {
+            for name in entry.pinned.iter_mut().chain(entry.order.iter_mut()) {
+                if *name == old_name {
+                    *name = new_name.clone();
+                    changed = true;
+                }
+            }
+        } else if pinned || entry.position(&old_name).is_some() {
+            entry.remove(&old_name);
+            if pinned {
+                orders.entry(new_folder).or_default().pinned.push(new_name);
+            }
+            changed = true;
+        }
+    }
+
+    if changed {
+        orders.retain(|_, entry| !entry.is_empty());
+        if let Err(e) = save_order(notes_dir, &orders) {
+            eprintln!("Failed to update note order: {}", e);
+        }
+    }
+}
+
+/// Forget a deleted note, or everything under a deleted folder
+pub fn remove_entries(notes_dir: &Path, path: &Path) {
+    let mut orders = load_order(notes_dir);
+    let before = orders.len();
+
+    let rel = PathBuf::from(paths::to_relative(notes_dir, path));
+    orders.retain(|key, _| !Path::new(key).starts_with(&rel));
+    let mut changed = orders.len() != before;
+
+    if let Some((folder, name)) = split(notes_dir, path) {
+        if let Some(entry) = orders.get_mut(&folder) {
+            if entry.is_pinned(&name) || entry.position(&name).is_some() {
+                entry.remove(&name);
+                changed = true;
+            }
+        }
+    }
+
+    if changed {
+        orders.retain(|_, entry| !entry.is_empty());
+        if let Err(e) = save_order(notes_dir, &orders) {
+            eprintln!("Failed to update note order: {}", e);
+        }
+    }
+}
+
+/// Pin a note to the top of its folder, or unpin it
+#[tauri::command]
+pub fn set_note_pinned(path: String, pinned: bool, state: State<AppState>) -> Result<(), String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let full_path = paths::resolve_note(&notes_dir, &path)?;
+    if !full_path.is_file() {
+        return Err(format!("Note not found: {}", path));
+    }
+
+    let (folder, name) = split(&notes_dir, &full_path).ok_or("Invalid note path")?;
+    let mut orders = load_order(&notes_dir);
+    let entry = orders.entry(folder).or_default();
+    entry.pinned.retain(|n| *n != name);
+    if pinned {
+        entry.pinned.push(name);
+    }
+    orders.retain(|_, entry| !entry.is_empty());
+    save_order(&notes_dir, &orders)
+}
+
+/// Save the order of the notes in `folder`, as arranged by dragging. `notes`
+/// lists the folder's note paths top to bottom; other folders' are ignored.
+#[tauri::command]
+pub fn reorder_notes(folder: String, notes: Vec<String>, state: State<AppState>) -> Result<(), String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let folder_path = paths::resolve(&notes_dir, &folder)?;
+    if !folder_path.is_dir() {
+        return Err(format!("Not a folder: {}", folder));
+    }
+
+    let mut order = Vec::new();
+    for path in &notes {
+        let full_path = paths::resolve_note(&notes_dir, path)?;
+        if full_path.parent() != Some(folder_path.as_path()) {
+            continue;
+        }
+        if let Some((_, name)) = split(&notes_dir, &full_path) {
+            if !order.contains(&name) {
+                order.push(name);
+            }
+        }
+    }
+
+    let mut orders = load_order(&notes_dir);
+    orders.entry(paths::to_relative(&notes_dir, &folder_path)).or_default().order = order;
+    orders.retain(|_, entry| !entry.is_empty());
+    save_order(&notes_dir, &orders)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn folder(pinned: &[&str], order: &[&str]) -> FolderOrder {
+        FolderOrder {
+            pinned: pinned.iter().map(|s| s.to_string()).collect(),
+            order: order.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    fn vault(orders: &[(&str, FolderOrder)]) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let orders: HashMap<String, FolderOrder> = orders.iter().map(|(k, v)| (k.to_string(), v.clone())).collect();
+        save_order(dir.path(), &orders).unwrap();
+        dir
+    }
+
+    fn order_of(root: &Path, folder: &str) -> (Vec<String>, Vec<String>) {
+        let order = folder_order(root, &root.join(folder));
+        (order.pinned, order.order)
+    }
+
+    fn keys(root: &Path) -> Vec<String> {
+        let mut keys: Vec<String> = load_order(root).into_keys().collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn rename_in_place_keeps_pin_and_position() {
+        let dir = vault(&[("inbox", folder(&["b.md"], &["a.md", "b.md", "c.md"]))]);
+        let root = dir.path();
+
+        move_entries(root, &[(root.join("inbox/b.md"), root.join("inbox/z.md"))]);
+        assert_eq!(order_of(root, "inbox"), (vec!["z.md".into()], vec!["a.md".into(), "z.md".into(), "c.md".into()]));
+    }
+
+    #[test]
+    fn move_to_another_folder_keeps_only_the_pin() {
+        let dir = vault(&[
+            ("inbox", folder(&["a.md"], &["b.md", "a.md"])),
+            ("work", folder(&["w.md"], &["w.md"])),
+        ]);
+        let root = dir.path();
+
+        move_entries(root, &[(root.join("inbox/a.md"), root.join("work/a.md"))]);
+        assert_eq!(order_of(root, "inbox"), (vec![], vec!["b.md".into()]));
+        assert_eq!(order_of(root, "work"), (vec!["w.md".into(), "a.md".into()], vec!["w.md".into()]));
+
+        // Unpinned notes leave their position behind, and emptied folders go
+        move_entries(root, &[(root.join("inbox/b.md"), root.join("work/b.md"))]);
+        assert_eq!(keys(root), ["work"]);
+        assert_eq!(order_of(root, "work").1, ["w.md"]);
+    }
+
+    #[test]
+    fn folder_rename_moves_its_subtree() {
+        let dir = vault(&[
+            ("a", folder(&["x.md"], &[])),
+            ("a/sub", folder(&[], &["s.md"])),
+            ("ab", folder(&["y.md"], &[])),
+        ]);
+        let root = dir.path();
+        fs::create_dir_all(root.join("c")).unwrap();
+
+        move_entries(root, &[(root.join("a"), root.join("c"))]);
+        assert_eq!(keys(root), ["ab", "c", "c/sub"]);
+        assert_eq!(order_of(root, "c/sub").1, ["s.md"]);
+    }
+
+    #[test]
+    fn deleting_removes_a_note_or_a_subtree() {
+        let dir = vault(&[
+            ("a", folder(&["x.md"], &["x.md", "y.md"])),
+            ("a/sub", folder(&["s.md"], &[])),
+            ("ab", folder(&["y.md"], &[])),
+        ]);
+        let root = dir.path();
+
+        remove_entries(root, &root.join("a/x.md"));
+        assert_eq!(order_of(root, "a"), (vec![], vec!["y.md".into()]));
+
+        remove_entries(root, &root.join("a"));
+        assert_eq!(keys(root), ["ab"]);
+    }
+
+    #[test]
+    fn unknown_notes_leave_the_file_alone() {
+        let dir = vault(&[("a", folder(&["x.md"], &[]))]);
+        let root = dir.path();
+        let before = fs::read_to_string(order_path(root)).unwrap();
+
+        move_entries(root, &[(root.join("b/q.md"), root.join("c/q.md"))]);
+        remove_entries(root, &root.join("a/q.md"));
+        assert_eq!(fs::read_to_string(order_path(root)).unwrap(), before);
+    }
+}
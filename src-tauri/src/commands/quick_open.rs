@@ -0,0 +1,250 @@
+use crate::atomic;
+use crate::commands::index::lock_index;
+use crate::commands::notes::meta_dir;
+use crate::paths;
+use crate::AppState;
+use chrono::Utc;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tauri::State;
+
+// Fuzzy "go to note" over titles and paths, fzf style: every space separated
+// word of the query has to match the title or the path, in order of its
+// characters but not necessarily adjacent. Notes opened often and recently,
+// tracked in <notes_dir>/.onote/frecency.json, and recently modified notes
+// rank higher among similar matches.
+
+const DEFAULT_LIMIT: usize = 20;
+// Opens lose half their weight every week
+const FRECENCY_HALF_LIFE_DAYS: f64 = 7.0;
+// Path matches count for less than title matches
+const PATH_WEIGHT: f64 = 0.8;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuickOpenResult {
+    pub path: String,
+    pub title: String,
+    pub score: f64,
+    /// Byte ranges of matched characters in `title`
+    pub title_highlights: Vec<Range<usize>>,
+    /// Byte ranges of matched characters in `path`
+    pub path_highlights: Vec<Range<usize>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct OpenStats {
+    count: u32,
+    /// Unix seconds
+    last_opened: i64,
+}
+
+fn frecency_path(notes_dir: &Path) -> PathBuf {
+    meta_dir(notes_dir).join("frecency.json")
+}
+
+fn load_frecency(notes_dir: &Path) -> HashMap<String, OpenStats> {
+    fs::read_to_string(frecency_path(notes_dir))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_frecency(notes_dir: &Path, stats: &HashMap<String, OpenStats>) -> Result<(), String> {
+    fs::create_dir_all(meta_dir(notes_dir)).map_err(|e| e.to_string())?;
+    let content = serde_json::to_string_pretty(stats).map_err(|e| e.to_string())?;
+    atomic::write(&frecency_path(notes_dir), content).map_err(|e| e.to_string())
+}
+
+/// Carry open counts over to the new paths of moved notes (absolute old → new)
+pub fn move_entries(notes_dir: &Path, moves: &HashMap<PathBuf, PathBuf>) {
+    let mut stats = load_frecency(notes_dir);
+    let mut changed = false;
+
+    for (old, new) in moves {
+        if let Some(entry) = stats.remove(&paths::to_relative(notes_dir, old)) {
+            stats.insert(paths::to_relative(notes_dir, new), entry);
+            changed = true;
+        }
+    }
+
+    if changed {
+        if let Err(e) = save_frecency(notes_dir, &stats) {
+            eprintln!("Failed to update open history: {}", e);
+        }
+    }
+}
+
+// Opens, decayed by how long ago the last one was
+fn frecency(stats: &OpenStats, now: i64) -> f64 {
+    let days = (now - stats.last_opened).max(0) as f64 / 86_400.0;
+    stats.count as f64 * 0.5f64.powf(days / FRECENCY_HALF_LIFE_DAYS)
+}
+
+// Small bonus for notes edited in the last month
+fn recency(modified: SystemTime) -> f64 {
+    let days = SystemTime::now()
+        .duration_since(modified)
+        .map(|d| d.as_secs_f64() / 86_400.0)
+        .unwrap_or(0.0);
+    (30.0 - days).max(0.0) / 3.0
+}
+
+// Char indices from the matcher as merged byte ranges
+fn byte_ranges(text: &str, indices: &[usize]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for (i, (offset, c)) in text.char_indices().enumerate() {
+        if indices.binary_search(&i).is_err() {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(last) if last.end == offset => last.end = offset + c.len_utf8(),
+            _ => ranges.push(offset..offset + c.len_utf8()),
+        }
+    }
+    ranges
+}
+
+fn merge(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_by_key(|r| r.start);
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Notes whose title or path fuzzily match `query`, best first. An empty query
+/// lists the most frequently and recently opened notes.
+#[tauri::command]
+pub fn quick_open(query: String, limit: Option<usize>, state: State<AppState>) -> Result<Vec<QuickOpenResult>, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let stats = load_frecency(&notes_dir);
+    let now = Utc::now().timestamp();
+    let matcher = SkimMatcherV2::default();
+    let words: Vec<&str> = query.split_whitespace().collect();
+
+    let index = lock_index(&state);
+    let mut results: Vec<QuickOpenResult> = index
+        .iter()
+        .filter_map(|(rel, note)| {
+            let path = rel.replace('\\', "/");
+            let mut score = 0.0;
+            let mut title_highlights = Vec::new();
+            let mut path_highlights = Vec::new();
+
+            for word in &words {
+                let in_title = matcher.fuzzy_indices(&note.title, word);
+                let in_path = matcher.fuzzy_indices(&path, word);
+                let title_score = in_title.as_ref().map(|(s, _)| *s as f64);
+                let path_score = in_path.as_ref().map(|(s, _)| *s as f64 * PATH_WEIGHT);
+
+                match (title_score, path_score) {
+                    (None, None) => return None,
+                    (Some(t), p) if t >= p.unwrap_or(0.0) => {
+                        score += t;
+                        title_highlights.extend(byte_ranges(&note.title, &in_title?.1));
+                    }
+                    (_, p) => {
+                        score += p.unwrap_or(0.0);
+                        path_highlights.extend(byte_ranges(&path, &in_path?.1));
+                    }
+                }
+            }
+
+            let opened = stats.get(rel).map(|s| frecency(s, now)).unwrap_or(0.0);
+            if words.is_empty() && opened == 0.0 {
+                return None;
+            }
+            score += opened * 10.0 + recency(note.modified);
+
+            Some(QuickOpenResult {
+                path,
+                title: note.title.clone(),
+                score,
+                title_highlights: merge(title_highlights),
+                path_highlights: merge(path_highlights),
+            })
+        })
+        .collect();
+    drop(index);
+
+    results.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+    results.truncate(limit.unwrap_or(DEFAULT_LIMIT));
+    Ok(results)
+}
+
+/// Count an open of the note at `path` towards its quick open ranking
+#[tauri::command]
+pub fn record_note_open(path: String, state: State<AppState>) -> Result<(), String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let full_path = paths::resolve_note(&notes_dir, &path)?;
+    let rel = paths::to_relative(&notes_dir, &full_path);
+
+    let mut stats = load_frecency(&notes_dir);
+    let entry = stats.entry(rel).or_default();
+    entry.count = entry.count.saturating_add(1);
+    entry.last_opened = Utc::now().timestamp();
+
+    // Forget notes that no longer exist
+    stats.retain(|rel, _| notes_dir.join(rel).exists());
+    save_frecency(&notes_dir, &stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn highlighted<'a>(text: &'a str, ranges: &[Range<usize>]) -> Vec<&'a str> {
+        ranges.iter().map(|r| &text[r.clone()]).collect()
+    }
+
+    #[test]
+    fn char_indices_become_byte_ranges() {
+        let title = "Café über naïve";
+        // "é ü" and "ï": runs of matched chars join into one range
+        let ranges = byte_ranges(title, &[3, 4, 5, 12]);
+        assert_eq!(ranges, [3..8, 14..16]);
+        assert_eq!(highlighted(title, &ranges), ["é ü", "ï"]);
+
+        assert_eq!(byte_ranges("日本語のメモ", &[0, 2, 4]), [0..3, 6..9, 12..15]);
+        assert!(byte_ranges(title, &[]).is_empty());
+        // Indices past the end are ignored
+        assert_eq!(byte_ranges("ab", &[1, 7]), vec![1..2]);
+    }
+
+    #[test]
+    fn matcher_indices_line_up_with_non_ascii_titles() {
+        let matcher = SkimMatcherV2::default();
+        let title = "Résumé für Zoë";
+        let (_, indices) = matcher.fuzzy_indices(title, "zoë").unwrap();
+        assert_eq!(highlighted(title, &byte_ranges(title, &indices)), ["Zoë"]);
+
+        let (_, indices) = matcher.fuzzy_indices(title, "für").unwrap();
+        assert_eq!(highlighted(title, &byte_ranges(title, &indices)), ["für"]);
+    }
+
+    #[test]
+    fn ranges_from_several_words_merge() {
+        // Out of order, overlapping, touching and apart
+        assert_eq!(merge(vec![6..9, 0..3, 2..4, 4..5, 12..13]), [0..5, 6..9, 12..13]);
+        assert_eq!(merge(vec![0..10, 2..4]), vec![0..10]);
+        assert!(merge(Vec::new()).is_empty());
+
+        let title = "Über Straße";
+        let matcher = SkimMatcherV2::default();
+        let mut ranges = Vec::new();
+        for word in ["straße", "Übe", "ber"] {
+            ranges.extend(byte_ranges(title, &matcher.fuzzy_indices(title, word).unwrap().1));
+        }
+        assert_eq!(highlighted(title, &merge(ranges)), ["Über", "Straße"]);
+    }
+}
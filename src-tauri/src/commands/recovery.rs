@@ -0,0 +1,127 @@
+use crate::atomic;
+use crate::commands::history;
+use crate::paths;
+use crate::AppState;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tauri::State;
+
+// Temp files from interrupted note writes. They may hold newer text than the
+// note itself, so they are reported rather than silently deleted.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryFile {
+    pub temp_path: String,
+    pub note_path: String,
+    pub size_bytes: u64,
+    pub modified: String,
+    pub note_exists: bool,
+    pub preview: String,
+}
+
+fn is_note(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext == "md" || ext == "txt")
+        .unwrap_or(false)
+}
+
+fn describe(notes_dir: &Path, temp: &Path, target: &Path) -> RecoveryFile {
+    let metadata = fs::metadata(temp).ok();
+    let modified: DateTime<Local> = metadata
+        .as_ref()
+        .and_then(|m| m.modified().ok())
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+        .into();
+    let content = fs::read_to_string(temp).unwrap_or_default();
+
+    RecoveryFile {
+        temp_path: paths::to_relative(notes_dir, temp),
+        note_path: paths::to_relative(notes_dir, target),
+        size_bytes: metadata.map(|m| m.len()).unwrap_or(0),
+        modified: modified.to_rfc3339(),
+        note_exists: target.exists(),
+        preview: content.chars().take(200).collect(),
+    }
+}
+
+/// Find leftovers from interrupted writes. Leftovers of app metadata are
+/// removed straight away; those of notes are returned for the user to review.
+pub fn scan(notes_dir: &Path) -> Vec<RecoveryFile> {
+    let mut found = Vec::new();
+
+    for temp in atomic::find_leftovers(notes_dir) {
+        let target = match atomic::target_of(&temp) {
+            Some(target) => target,
+            None => continue,
+        };
+
+        // Notes are never inside hidden folders; anything there is app metadata
+        let note_path = paths::to_relative(notes_dir, &target);
+        if paths::resolve_note(notes_dir, &note_path).is_ok() {
+            found.push(describe(notes_dir, &temp, &target));
+        } else if fs::remove_file(&temp).is_ok() {
+            println!("Removed leftover temp file: {:?}", temp);
+        }
+    }
+
+    if !found.is_empty() {
+        println!("Found {} interrupted note writes to recover", found.len());
+    }
+
+    found.sort_by(|a, b| b.modified.cmp(&a.modified));
+    found
+}
+
+// Map a temp path from the webview back onto the filesystem. Temp files are
+// hidden, so the folder is resolved normally and the name checked on its own.
+fn resolve_temp(notes_dir: &Path, temp_path: &str) -> Result<(PathBuf, PathBuf), String> {
+    let (folder, name) = temp_path
+        .rsplit_once(['/', '\\'])
+        .unwrap_or(("", temp_path));
+
+    let temp = paths::resolve(notes_dir, folder)?.join(name);
+    let target = atomic::target_of(&temp)
+        .filter(|t| is_note(t))
+        .ok_or_else(|| format!("Not a recovery file: {}", temp_path))?;
+
+    if !temp.is_file() {
+        return Err(format!("Recovery file not found: {}", temp_path));
+    }
+
+    Ok((temp, target))
+}
+
+#[tauri::command]
+pub fn list_recovery_files(state: State<AppState>) -> Result<Vec<RecoveryFile>, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    Ok(scan(&notes_dir))
+}
+
+/// Either put the temp file's content into its note (`restore`) or throw it away.
+/// A restored note's previous content is kept in its version history.
+#[tauri::command]
+pub fn resolve_recovery_file(
+    temp_path: String,
+    restore: bool,
+    state: State<AppState>,
+) -> Result<Option<String>, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let (temp, target) = resolve_temp(&notes_dir, &temp_path)?;
+
+    if !restore {
+        fs::remove_file(&temp).map_err(|e| e.to_string())?;
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&temp).map_err(|e| e.to_string())?;
+    history::snapshot_file(&notes_dir, &target, &content);
+    atomic::write(&target, &content).map_err(|e| e.to_string())?;
+    fs::remove_file(&temp).map_err(|e| e.to_string())?;
+
+    let rel_path = paths::to_relative(&notes_dir, &target);
+    println!("Recovered interrupted write: {}", rel_path);
+    Ok(Some(rel_path))
+}
@@ -0,0 +1,430 @@
+use crate::atomic;
+use crate::commands::history;
+use crate::commands::notes::{content_version, meta_dir};
+use crate::commands::search::{line_number, line_span, scan};
+use crate::frontmatter;
+use crate::paths;
+use crate::pattern::{self, MatchOptions, Pattern, Replacement};
+use crate::AppState;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+// Find and replace across notes, in two steps: `preview_replace` lists every
+// change per note with the note's version, then `replace_in_notes` applies the
+// previewed notes that haven't changed since. The files it modifies are first
+// copied to <notes_dir>/.onote/replace/<id>/, next to manifest.json, so
+// `undo_replace` can put them back.
+
+// Replacements whose backups are kept, newest first
+const MAX_BACKUPS: usize = 20;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplacePreview {
+    pub files: Vec<ReplaceFile>,
+    pub replacements: usize,
+    /// False if notes were left out for their size or the search timed out
+    pub complete: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplaceFile {
+    pub path: String,
+    pub title: String,
+    /// Version the preview was made from, to pass back to `replace_in_notes`
+    pub version: String,
+    pub changes: Vec<ReplaceChange>,
+}
+
+/// The lines around one or more neighbouring matches, before and after
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplaceChange {
+    /// First line, starting at 1
+    pub line_number: usize,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplaceTarget {
+    pub path: String,
+    pub version: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplaceSummary {
+    /// Backup to pass to `undo_replace`, if anything was changed
+    pub backup_id: Option<String>,
+    pub changed: Vec<String>,
+    pub replacements: usize,
+    /// Notes edited since the preview, gone, or failing to write, which were
+    /// left alone
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaceBackup {
+    pub id: String,
+    pub created_at: String,
+    pub query: String,
+    pub replacement: String,
+    pub files: Vec<BackedUpFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackedUpFile {
+    pub path: String,
+    /// Version written by the replace; undo leaves notes edited since alone
+    pub replaced_version: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UndoSummary {
+    pub restored: Vec<String>,
+    pub skipped: Vec<String>,
+}
+
+fn backups_dir(notes_dir: &Path) -> PathBuf {
+    meta_dir(notes_dir).join("replace")
+}
+
+fn manifest_path(notes_dir: &Path, id: &str) -> PathBuf {
+    backups_dir(notes_dir).join(id).join("manifest.json")
+}
+
+// Originals are stored by their position in the manifest
+fn original_path(notes_dir: &Path, id: &str, n: usize) -> PathBuf {
+    backups_dir(notes_dir).join(id).join(format!("{}.md", n))
+}
+
+// Record the manifest before every write, so a failure halfway still leaves an
+// undoable backup of what was changed
+fn write_manifest(notes_dir: &Path, backup: &ReplaceBackup) -> Result<(), String> {
+    let manifest = serde_json::to_string_pretty(backup).map_err(|e| e.to_string())?;
+    atomic::write(&manifest_path(notes_dir, &backup.id), manifest).map_err(|e| e.to_string())
+}
+
+// Keep the original of a note about to be replaced and list it in the manifest
+fn back_up(notes_dir: &Path, backup: &mut ReplaceBackup, path: &str, content: &str, new_content: &str) -> Result<(), String> {
+    let original = original_path(notes_dir, &backup.id, backup.files.len());
+    fs::create_dir_all(backups_dir(notes_dir).join(&backup.id)).map_err(|e| e.to_string())?;
+    atomic::write(&original, content).map_err(|e| e.to_string())?;
+    backup.files.push(BackedUpFile {
+        path: path.to_string(),
+        replaced_version: content_version(new_content),
+    });
+    if let Err(e) = write_manifest(notes_dir, backup) {
+        backup.files.pop();
+        return Err(e);
+    }
+    Ok(())
+}
+
+fn read_backup(notes_dir: &Path, id: &str) -> Option<ReplaceBackup> {
+    let content = fs::read_to_string(manifest_path(notes_dir, id)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn new_backup_id(notes_dir: &Path) -> String {
+    let stamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let mut id = stamp.clone();
+    let mut counter = 1;
+    while backups_dir(notes_dir).join(&id).exists() {
+        id = format!("{}-{}", stamp, counter);
+        counter += 1;
+    }
+    id
+}
+
+// Drop all but the newest backups
+fn prune_backups(notes_dir: &Path) {
+    let mut ids: Vec<String> = match fs::read_dir(backups_dir(notes_dir)) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect(),
+        Err(_) => return,
+    };
+    // Ids start with a timestamp, so they sort by age
+    ids.sort();
+    ids.reverse();
+
+    for id in ids.into_iter().skip(MAX_BACKUPS) {
+        fs::remove_dir_all(backups_dir(notes_dir).join(id)).ok();
+    }
+}
+
+// Replacements in the body of `content`, leaving any frontmatter alone
+fn body_replacements(pattern: &Pattern, content: &str, replacement: &str) -> Vec<Replacement> {
+    let body_start = content.len() - frontmatter::parse(content).1.len();
+    pattern
+        .replacements(&content[body_start..], replacement)
+        .into_iter()
+        .map(|(r, with)| (r.start + body_start..r.end + body_start, with))
+        .collect()
+}
+
+// Start of the line after the one ending at `line_end`
+fn next_line(content: &str, line_end: usize) -> usize {
+    content[line_end..].find('\n').map(|i| line_end + i + 1).unwrap_or(content.len())
+}
+
+// Matches on the same or adjacent lines are shown as one change
+fn changes(content: &str, replacements: &[Replacement]) -> Vec<ReplaceChange> {
+    let mut groups: Vec<(Range<usize>, Vec<Replacement>)> = Vec::new();
+    for (range, with) in replacements {
+        let span = line_span(content, range);
+        match groups.last_mut() {
+            Some((group, members)) if span.start <= next_line(content, group.end) => {
+                group.end = group.end.max(span.end);
+                members.push((range.clone(), with.clone()));
+            }
+            _ => groups.push((span, vec![(range.clone(), with.clone())])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(span, members)| {
+            let shifted: Vec<Replacement> = members
+                .into_iter()
+                .map(|(r, with)| (r.start - span.start..r.end - span.start, with))
+                .collect();
+            let before = &content[span.clone()];
+            ReplaceChange {
+                line_number: line_number(content, span.start),
+                before: before.to_string(),
+                after: pattern::apply(before, &shifted),
+            }
+        })
+        .collect()
+}
+
+/// Every change replacing `query` with `replacement` would make, per note.
+/// Nothing is written.
+#[tauri::command]
+pub fn preview_replace(
+    query: String,
+    replacement: String,
+    options: Option<MatchOptions>,
+    state: State<AppState>,
+) -> Result<ReplacePreview, String> {
+    let pattern = Pattern::new(&query, options.unwrap_or_default())?;
+    let (notes, complete) = scan(&state, &pattern);
+
+    let mut total = 0;
+    let files = notes
+        .into_iter()
+        .map(|note| {
+            let replacements = body_replacements(&pattern, &note.content, &replacement);
+            total += replacements.len();
+
+            ReplaceFile {
+                version: content_version(&note.content),
+                changes: changes(&note.content, &replacements),
+                path: note.path,
+                title: note.title,
+            }
+        })
+        .collect();
+
+    Ok(ReplacePreview {
+        files,
+        replacements: total,
+        complete,
+    })
+}
+
+/// Replace `query` with `replacement` in the previewed notes, backing each up
+/// first. Notes that changed since their preview are skipped.
+#[tauri::command]
+pub fn replace_in_notes(
+    query: String,
+    replacement: String,
+    options: Option<MatchOptions>,
+    files: Vec<ReplaceTarget>,
+    state: State<AppState>,
+) -> Result<ReplaceSummary, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let pattern = Pattern::new(&query, options.unwrap_or_default())?;
+
+    let id = new_backup_id(&notes_dir);
+    let mut backup = ReplaceBackup {
+        id: id.clone(),
+        created_at: Local::now().to_rfc3339(),
+        query,
+        replacement,
+        files: Vec::new(),
+    };
+    let mut summary = ReplaceSummary {
+        backup_id: None,
+        changed: Vec::new(),
+        replacements: 0,
+        skipped: Vec::new(),
+    };
+
+    // A note that can't be written is skipped; the notes already changed stay
+    // changed and undoable
+    for target in files {
+        let full_path = match paths::resolve_note(&notes_dir, &target.path) {
+            Ok(path) => path,
+            Err(_) => {
+                summary.skipped.push(target.path);
+                continue;
+            }
+        };
+        let content = match fs::read_to_string(&full_path) {
+            Ok(content) if content_version(&content) == target.version => content,
+            _ => {
+                summary.skipped.push(target.path);
+                continue;
+            }
+        };
+
+        let replacements = body_replacements(&pattern, &content, &backup.replacement);
+        if replacements.is_empty() {
+            continue;
+        }
+        let new_content = pattern::apply(&content, &replacements);
+
+        if let Err(e) = back_up(&notes_dir, &mut backup, &target.path, &content, &new_content) {
+            eprintln!("Failed to back up {} before replacing: {}", target.path, e);
+            summary.skipped.push(target.path);
+            continue;
+        }
+
+        history::snapshot_file(&notes_dir, &full_path, &new_content);
+        if let Err(e) = atomic::write(&full_path, &new_content) {
+            eprintln!("Failed to replace in {}: {}", target.path, e);
+            backup.files.pop();
+            write_manifest(&notes_dir, &backup).ok();
+            summary.skipped.push(target.path);
+            continue;
+        }
+        state.index.lock().unwrap().update(&full_path);
+
+        summary.replacements += replacements.len();
+        summary.changed.push(target.path);
+    }
+
+    if !backup.files.is_empty() {
+        println!(
+            "Replaced {} matches in {} notes (backup {})",
+            summary.replacements,
+            summary.changed.len(),
+            id
+        );
+        summary.backup_id = Some(id);
+        prune_backups(&notes_dir);
+    } else {
+        // Nothing was changed, drop whatever a failed note left behind
+        fs::remove_dir_all(backups_dir(&notes_dir).join(&id)).ok();
+    }
+
+    Ok(summary)
+}
+
+/// Replacements that can still be undone, newest first
+#[tauri::command]
+pub fn list_replace_backups(state: State<AppState>) -> Result<Vec<ReplaceBackup>, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let mut backups: Vec<ReplaceBackup> = match fs::read_dir(backups_dir(&notes_dir)) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| read_backup(&notes_dir, &e.file_name().to_string_lossy()))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    backups.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(backups)
+}
+
+/// Put back the notes a replace changed. Notes edited since are left alone and
+/// reported as skipped; the backup is kept until every note is restored.
+#[tauri::command]
+pub fn undo_replace(backup_id: String, state: State<AppState>) -> Result<UndoSummary, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    paths::validate_name(&backup_id)?;
+    let backup = read_backup(&notes_dir, &backup_id).ok_or("Replace backup not found")?;
+
+    let mut summary = UndoSummary::default();
+    for (n, file) in backup.files.iter().enumerate() {
+        let full_path = match paths::resolve_note(&notes_dir, &file.path) {
+            Ok(path) => path,
+            Err(_) => {
+                summary.skipped.push(file.path.clone());
+                continue;
+            }
+        };
+        let current = fs::read_to_string(&full_path).ok();
+        let original = fs::read_to_string(original_path(&notes_dir, &backup_id, n));
+
+        match (current, original) {
+            (Some(current), Ok(original)) if content_version(&current) == file.replaced_version => {
+                history::snapshot_file(&notes_dir, &full_path, &original);
+                if let Err(e) = atomic::write(&full_path, &original) {
+                    eprintln!("Failed to restore {}: {}", file.path, e);
+                    summary.skipped.push(file.path.clone());
+                    continue;
+                }
+                state.index.lock().unwrap().update(&full_path);
+                summary.restored.push(file.path.clone());
+            }
+            _ => summary.skipped.push(file.path.clone()),
+        }
+    }
+
+    if summary.skipped.is_empty() {
+        fs::remove_dir_all(backups_dir(&notes_dir).join(&backup_id)).ok();
+    }
+    println!("Undid replace {}: {} notes restored", backup_id, summary.restored.len());
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preview(content: &str, pattern: &str, replacement: &str) -> Vec<(usize, String, String)> {
+        let pattern = Pattern::new(pattern, MatchOptions::default()).unwrap();
+        let replacements = body_replacements(&pattern, content, replacement);
+        changes(content, &replacements)
+            .into_iter()
+            .map(|c| (c.line_number, c.before, c.after))
+            .collect()
+    }
+
+    #[test]
+    fn groups_matches_on_the_same_and_adjacent_lines() {
+        let content = "a x x\nb x\n\nc\nx d\n";
+        assert_eq!(
+            preview(content, "x", "y"),
+            [
+                (1, "a x x\nb x".to_string(), "a y y\nb y".to_string()),
+                (5, "x d".to_string(), "y d".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn groups_adjacent_crlf_lines() {
+        let content = "x\r\nx\r\n\r\nx";
+        assert_eq!(
+            preview(content, "x", "y"),
+            [(1, "x\r\nx".to_string(), "y\r\ny".to_string()), (4, "x".to_string(), "y".to_string())]
+        );
+    }
+
+    #[test]
+    fn leaves_frontmatter_alone() {
+        let content = "---\ntitle: x\n---\nx\n";
+        let pattern = Pattern::new("x", MatchOptions::default()).unwrap();
+        let replacements = body_replacements(&pattern, content, "y");
+        assert_eq!(pattern::apply(content, &replacements), "---\ntitle: x\n---\ny\n");
+        assert_eq!(preview(content, "x", "y"), [(4, "x".to_string(), "y".to_string())]);
+    }
+}
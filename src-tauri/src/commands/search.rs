@@ -0,0 +1,706 @@
+use crate::commands::index::{lock_index, IndexedNote, NoteIndex};
+use crate::commands::notes::meta_dir;
+use crate::frontmatter;
+use crate::pattern::{MatchOptions, Pattern};
+use crate::query::{self, Filter, Has};
+use crate::AppState;
+use chrono::{Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::ops::{Bound, Range};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tantivy::collector::{DocSetCollector, TopDocs};
+use tantivy::directory::MmapDirectory;
+use tantivy::query::{
+    AllQuery, BooleanQuery, BoostQuery, FuzzyTermQuery, Occur, PhraseQuery, Query, RangeQuery, TermQuery,
+};
+use tantivy::schema::{
+    Field, IndexRecordOption, Schema, TantivyDocument, TextFieldIndexing, TextOptions, Value, FAST, INDEXED, STORED,
+    STRING,
+};
+use tantivy::tokenizer::TextAnalyzer;
+use tantivy::{doc, Index, IndexReader, IndexWriter, ReloadPolicy, Term};
+use tauri::State;
+
+// Full-text search over the vault, backed by a tantivy index in
+// <notes_dir>/.onote/search. Words are stemmed, so "meetings" finds "meeting";
+// a second, unstemmed copy of each field serves `prefix*` queries. The filters
+// of the query language (see `query.rs`) get fields of their own.
+//
+// The index is brought in line with the note index before every search, which
+// in turn is kept current by saves and the watcher.
+//
+// With any of the match options set (case-sensitive, whole word, regex) the
+// search text is a pattern instead, matched exactly against every note body
+// without the index; see `pattern.rs`.
+
+const TITLE_BOOST: f32 = 3.0;
+const DEFAULT_LIMIT: usize = 50;
+const SNIPPETS_PER_NOTE: usize = 3;
+const SNIPPET_CHARS: usize = 160;
+const WRITER_MEMORY: usize = 15_000_000;
+// Exact matching reads every note; it skips huge ones and gives up after a while
+const SCAN_MAX_BYTES: u64 = 5_000_000;
+const SCAN_TIME_LIMIT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub results: Vec<SearchResult>,
+    /// False if a pattern search left notes out for their size or timed out
+    pub complete: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub path: String,
+    pub title: String,
+    pub score: f32,
+    /// Byte ranges of matched words in `title`
+    pub title_highlights: Vec<Range<usize>>,
+    pub snippets: Vec<SearchSnippet>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchSnippet {
+    /// Line in the note, starting at 1
+    pub line_number: usize,
+    pub text: String,
+    /// Byte ranges of matched words in `text`
+    pub highlights: Vec<Range<usize>>,
+}
+
+#[derive(Clone, Copy)]
+struct Fields {
+    path: Field,
+    modified: Field,
+    size: Field,
+    // Lines taken by frontmatter, to number snippet lines from the top of the file
+    body_line: Field,
+    title: Field,
+    body: Field,
+    title_words: Field,
+    body_words: Field,
+    // Every folder the note is in, lowercased: `work` and `work/meetings`
+    folder: Field,
+    // Tags with their parents: `project` and `project/alpha`
+    tag: Field,
+    // `audio`, `todo`
+    has: Field,
+}
+
+fn text_options(tokenizer: &str, positions: bool, stored: bool) -> TextOptions {
+    let record = if positions {
+        IndexRecordOption::WithFreqsAndPositions
+    } else {
+        IndexRecordOption::WithFreqs
+    };
+    let options = TextOptions::default()
+        .set_indexing_options(TextFieldIndexing::default().set_tokenizer(tokenizer).set_index_option(record));
+    if stored {
+        options.set_stored()
+    } else {
+        options
+    }
+}
+
+fn schema() -> (Schema, Fields) {
+    let mut builder = Schema::builder();
+    let fields = Fields {
+        path: builder.add_text_field("path", STRING | STORED),
+        modified: builder.add_u64_field("modified", INDEXED | FAST | STORED),
+        size: builder.add_u64_field("size", STORED),
+        body_line: builder.add_u64_field("body_line", STORED),
+        title: builder.add_text_field("title", text_options("en_stem", true, true)),
+        body: builder.add_text_field("body", text_options("en_stem", true, true)),
+        title_words: builder.add_text_field("title_words", text_options("default", false, false)),
+        body_words: builder.add_text_field("body_words", text_options("default", false, false)),
+        folder: builder.add_text_field("folder", STRING),
+        tag: builder.add_text_field("tag", STRING),
+        has: builder.add_text_field("has", STRING),
+    };
+    (builder.build(), fields)
+}
+
+fn nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0)
+}
+
+// Start of a day in local time
+fn day_start(day: NaiveDate) -> u64 {
+    day.and_hms_opt(0, 0, 0)
+        .and_then(|start| Local.from_local_datetime(&start).earliest())
+        .and_then(|start| start.timestamp_nanos_opt())
+        .map(|n| n.max(0) as u64)
+        .unwrap_or(0)
+}
+
+// `a/b/c` -> `a`, `a/b`, `a/b/c`
+fn with_ancestors(path: &str) -> Vec<String> {
+    let mut all = Vec::new();
+    let mut end = 0;
+    for segment in path.split('/') {
+        end += segment.len();
+        all.push(path[..end].to_string());
+        end += 1;
+    }
+    all
+}
+
+pub struct SearchIndex {
+    notes_dir: PathBuf,
+    index: Index,
+    reader: IndexReader,
+    writer: IndexWriter,
+    fields: Fields,
+    // (modified, size) of each indexed note, by relative path
+    indexed: HashMap<String, (u64, u64)>,
+}
+
+// What a sync has to do, taken from the note index while it is locked so the
+// indexing itself runs without it
+struct SyncPlan {
+    changed: Vec<(String, IndexedNote)>,
+    removed: Vec<String>,
+}
+
+impl SearchIndex {
+    pub fn open(notes_dir: &Path) -> Result<Self, String> {
+        let dir = meta_dir(notes_dir).join("search");
+        let (schema, fields) = schema();
+
+        let open = || -> tantivy::Result<Index> {
+            fs::create_dir_all(&dir)?;
+            Index::open_or_create(MmapDirectory::open(&dir)?, schema.clone())
+        };
+        // A damaged index or one from an older schema is rebuilt from the notes
+        let index = match open() {
+            Ok(index) => index,
+            Err(e) => {
+                println!("Rebuilding search index: {}", e);
+                fs::remove_dir_all(&dir).ok();
+                open().map_err(|e| e.to_string())?
+            }
+        };
+
+        let writer = index
+            .writer_with_num_threads(1, WRITER_MEMORY)
+            .map_err(|e| format!("Search index is in use: {}", e))?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()
+            .map_err(|e: tantivy::TantivyError| e.to_string())?;
+
+        let mut search = SearchIndex {
+            notes_dir: notes_dir.to_path_buf(),
+            index,
+            reader,
+            writer,
+            fields,
+            indexed: HashMap::new(),
+        };
+        search.indexed = search.load_stamps().map_err(|e| e.to_string())?;
+        Ok(search)
+    }
+
+    fn load_stamps(&self) -> tantivy::Result<HashMap<String, (u64, u64)>> {
+        let searcher = self.reader.searcher();
+        let mut stamps = HashMap::new();
+
+        for address in searcher.search(&AllQuery, &DocSetCollector)? {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let path = doc.get_first(self.fields.path).and_then(|v| v.as_str());
+            let modified = doc.get_first(self.fields.modified).and_then(|v| v.as_u64());
+            let size = doc.get_first(self.fields.size).and_then(|v| v.as_u64());
+            if let (Some(path), Some(modified), Some(size)) = (path, modified, size) {
+                stamps.insert(path.to_string(), (modified, size));
+            }
+        }
+        Ok(stamps)
+    }
+
+    /// Notes that changed since they were indexed, and notes that no longer exist
+    fn plan(&self, notes: &NoteIndex) -> SyncPlan {
+        let changed = notes
+            .iter()
+            .filter(|(rel, note)| self.indexed.get(*rel) != Some(&(nanos(note.modified), note.size)))
+            .map(|(rel, note)| (rel.clone(), note.clone()))
+            .collect();
+        let removed = self
+            .indexed
+            .keys()
+            .filter(|rel| notes.get(rel).is_none())
+            .cloned()
+            .collect();
+        SyncPlan { changed, removed }
+    }
+
+    /// Re-index the changed notes of `plan` and drop the removed ones
+    fn sync(&mut self, plan: SyncPlan) -> Result<(), String> {
+        let f = self.fields;
+        let changed = plan.changed.len() + plan.removed.len();
+
+        for (rel, note) in &plan.changed {
+            let stamp = (nanos(note.modified), note.size);
+            self.writer.delete_term(Term::from_field_text(f.path, rel));
+            self.indexed.remove(rel);
+
+            let content = match fs::read_to_string(self.notes_dir.join(rel)) {
+                Ok(content) => content,
+                Err(_) => continue,
+            };
+            let body = frontmatter::parse(&content).1;
+            let body_line = content[..content.len() - body.len()].lines().count() as u64;
+
+            let mut doc = doc!(
+                f.path => rel.as_str(),
+                f.modified => stamp.0,
+                f.size => stamp.1,
+                f.body_line => body_line,
+                f.title => note.title.as_str(),
+                f.body => body,
+                f.title_words => note.title.as_str(),
+                f.body_words => body,
+            );
+            let rel_unix = rel.replace('\\', "/").to_lowercase();
+            if let Some((folder, _)) = rel_unix.rsplit_once('/') {
+                for folder in with_ancestors(folder) {
+                    doc.add_text(f.folder, folder);
+                }
+            }
+            let tags: HashSet<String> = note.tags.iter().flat_map(|t| with_ancestors(t)).collect();
+            for tag in tags {
+                doc.add_text(f.tag, tag);
+            }
+            if note.has_audio {
+                doc.add_text(f.has, "audio");
+            }
+            if note.open_tasks > 0 {
+                doc.add_text(f.has, "todo");
+            }
+
+            self.writer.add_document(doc).map_err(|e| e.to_string())?;
+            self.indexed.insert(rel.clone(), stamp);
+        }
+
+        for rel in plan.removed {
+            self.writer.delete_term(Term::from_field_text(f.path, &rel));
+            self.indexed.remove(&rel);
+        }
+
+        if changed > 0 {
+            self.writer.commit().map_err(|e| e.to_string())?;
+            self.reader.reload().map_err(|e| e.to_string())?;
+            println!("Search index updated ({} notes)", changed);
+        }
+        Ok(())
+    }
+
+    fn analyzer(&self, name: &str) -> TextAnalyzer {
+        self.index
+            .tokenizers()
+            .get(name)
+            .expect("built-in tokenizer is registered")
+    }
+
+    // Analyzed terms of `text`, in order
+    fn terms(&self, field: Field, analyzer: &mut TextAnalyzer, text: &str) -> Vec<Term> {
+        let mut terms = Vec::new();
+        let mut stream = analyzer.token_stream(text);
+        while let Some(token) = stream.next() {
+            terms.push(Term::from_field_text(field, &token.text));
+        }
+        terms
+    }
+
+    // Query for a word, phrase or prefix in one stemmed/unstemmed field pair
+    fn text_query(&self, text: &query::Query, stemmed: Field, words: Field) -> Option<Box<dyn Query>> {
+        match text {
+            query::Query::Prefix(prefix) => {
+                let mut analyzer = self.analyzer("default");
+                let term = self.terms(words, &mut analyzer, prefix).pop()?;
+                Some(Box::new(FuzzyTermQuery::new_prefix(term, 0, false)))
+            }
+            query::Query::Word(text) | query::Query::Phrase(text) => {
+                let mut analyzer = self.analyzer("en_stem");
+                let terms = self.terms(stemmed, &mut analyzer, text);
+                // A word like e-mail tokenizes to several terms and matches as a phrase
+                match terms.len() {
+                    0 => None,
+                    1 => Some(Box::new(TermQuery::new(
+                        terms.into_iter().next()?,
+                        IndexRecordOption::WithFreqs,
+                    ))),
+                    _ => Some(Box::new(PhraseQuery::new(terms))),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn filter_query(&self, filter: &Filter) -> Box<dyn Query> {
+        let f = self.fields;
+        let term = |field: Field, value: &str| -> Box<dyn Query> {
+            Box::new(TermQuery::new(Term::from_field_text(field, value), IndexRecordOption::Basic))
+        };
+        let modified = |n: u64| Term::from_field_u64(f.modified, n);
+
+        match filter {
+            Filter::Folder(folder) => term(f.folder, &folder.to_lowercase()),
+            Filter::Tag(tag) => term(f.tag, tag),
+            Filter::Has(Has::Audio) => term(f.has, "audio"),
+            Filter::Has(Has::Todo) => term(f.has, "todo"),
+            Filter::Before(day) => Box::new(RangeQuery::new(
+                Bound::Unbounded,
+                Bound::Excluded(modified(day_start(*day))),
+            )),
+            Filter::After(day) => Box::new(RangeQuery::new(
+                Bound::Included(modified(day_start(*day))),
+                Bound::Unbounded,
+            )),
+        }
+    }
+
+    /// The tantivy query for a parsed query. Text has to be found in the title
+    /// or the body, and title hits score higher. None if nothing constrains the
+    /// results, like a lone `-`.
+    fn build_query(&self, query: &query::Query) -> Option<Box<dyn Query>> {
+        let f = self.fields;
+        match query {
+            query::Query::And(items) => {
+                let mut clauses: Vec<(Occur, Box<dyn Query>)> = items
+                    .iter()
+                    .filter_map(|item| match item {
+                        query::Query::Not(inner) => Some((Occur::MustNot, self.build_query(inner)?)),
+                        _ => Some((Occur::Must, self.build_query(item)?)),
+                    })
+                    .collect();
+                if clauses.is_empty() {
+                    return None;
+                }
+                // Exclusions alone match nothing; they have to exclude from all notes
+                if !clauses.iter().any(|(occur, _)| *occur == Occur::Must) {
+                    clauses.push((Occur::Must, Box::new(AllQuery)));
+                }
+                Some(Box::new(BooleanQuery::new(clauses)))
+            }
+            query::Query::Or(items) => {
+                let clauses: Vec<(Occur, Box<dyn Query>)> = items
+                    .iter()
+                    .filter_map(|item| Some((Occur::Should, self.build_query(item)?)))
+                    .collect();
+                (!clauses.is_empty()).then(|| Box::new(BooleanQuery::new(clauses)) as Box<dyn Query>)
+            }
+            query::Query::Not(inner) => Some(Box::new(BooleanQuery::new(vec![
+                (Occur::Must, Box::new(AllQuery) as Box<dyn Query>),
+                (Occur::MustNot, self.build_query(inner)?),
+            ]))),
+            query::Query::Filter(filter) => Some(self.filter_query(filter)),
+            text => {
+                let title = self.text_query(text, f.title, f.title_words)?;
+                let body = self.text_query(text, f.body, f.body_words)?;
+                Some(Box::new(BooleanQuery::new(vec![
+                    (Occur::Should, Box::new(BoostQuery::new(title, TITLE_BOOST)) as Box<dyn Query>),
+                    (Occur::Should, body),
+                ])))
+            }
+        }
+    }
+
+    // Byte ranges of words in `text` that match any of the text parts
+    fn highlights(&self, parts: &[&query::Query], text: &str) -> Vec<Range<usize>> {
+        let mut stemmer = self.analyzer("en_stem");
+        let mut wanted: HashSet<String> = HashSet::new();
+        let mut prefixes: Vec<String> = Vec::new();
+        for part in parts {
+            match part {
+                query::Query::Word(words) | query::Query::Phrase(words) => {
+                    let mut stream = stemmer.token_stream(words);
+                    while let Some(token) = stream.next() {
+                        wanted.insert(token.text.clone());
+                    }
+                }
+                query::Query::Prefix(prefix) => prefixes.push(prefix.to_lowercase()),
+                _ => {}
+            }
+        }
+
+        let mut ranges = Vec::new();
+        let mut stream = stemmer.token_stream(text);
+        while let Some(token) = stream.next() {
+            let raw = text[token.offset_from..token.offset_to].to_lowercase();
+            if wanted.contains(&token.text) || prefixes.iter().any(|p| raw.starts_with(p.as_str())) {
+                ranges.push(token.offset_from..token.offset_to);
+            }
+        }
+        ranges
+    }
+
+    fn snippets(&self, parts: &[&query::Query], body: &str, body_line: usize) -> Vec<SearchSnippet> {
+        body.lines()
+            .enumerate()
+            .filter_map(|(i, line)| {
+                let highlights = self.highlights(parts, line);
+                if highlights.is_empty() {
+                    return None;
+                }
+                let (text, highlights) = window(line, highlights);
+                Some(SearchSnippet {
+                    line_number: body_line + i + 1,
+                    text,
+                    highlights,
+                })
+            })
+            .take(SNIPPETS_PER_NOTE)
+            .collect()
+    }
+
+    fn search(&self, query: &query::Query, limit: usize) -> Result<Vec<SearchResult>, String> {
+        let parts = query.positive_text();
+        let tantivy_query = match self.build_query(query) {
+            Some(q) => q,
+            None => return Ok(Vec::new()),
+        };
+
+        let searcher = self.reader.searcher();
+        let top = searcher
+            .search(&tantivy_query, &TopDocs::with_limit(limit))
+            .map_err(|e| e.to_string())?;
+
+        let f = self.fields;
+        let mut results = Vec::new();
+        for (score, address) in top {
+            let doc: TantivyDocument = searcher.doc(address).map_err(|e| e.to_string())?;
+            let text = |field: Field| {
+                doc.get_first(field)
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            let (path, title, body) = (text(f.path), text(f.title), text(f.body));
+            let body_line = doc.get_first(f.body_line).and_then(|v| v.as_u64()).unwrap_or(0);
+
+            results.push(SearchResult {
+                title_highlights: self.highlights(&parts, &title),
+                snippets: self.snippets(&parts, &body, body_line as usize),
+                path,
+                title,
+                score,
+            });
+        }
+
+        Ok(results)
+    }
+}
+
+// Cut long lines down to a window around the first highlight, shifting the ranges
+fn window(line: &str, highlights: Vec<Range<usize>>) -> (String, Vec<Range<usize>>) {
+    if line.len() <= SNIPPET_CHARS {
+        return (line.to_string(), highlights);
+    }
+
+    let first = highlights.first().map(|r| r.start).unwrap_or(0);
+    let mut start = first.saturating_sub(SNIPPET_CHARS / 4);
+    while !line.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (start + SNIPPET_CHARS).min(line.len());
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    let shifted = highlights
+        .into_iter()
+        .filter(|r| r.start >= start && r.end <= end)
+        .map(|r| r.start - start..r.end - start)
+        .collect();
+    (line[start..end].to_string(), shifted)
+}
+
+/// A note read for exact matching
+pub struct ScannedNote {
+    pub path: String,
+    pub title: String,
+    pub content: String,
+    /// Matches in `content`, all within the body after any frontmatter
+    pub matches: Vec<Range<usize>>,
+}
+
+/// Notes whose body matches `pattern`, in path order. The flag is false if
+/// notes were skipped for their size or because the time limit ran out.
+pub fn scan(state: &AppState, pattern: &Pattern) -> (Vec<ScannedNote>, bool) {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let index = lock_index(state);
+    let mut candidates: Vec<(String, String)> = index
+        .iter()
+        .filter(|(_, note)| note.size <= SCAN_MAX_BYTES)
+        .map(|(rel, note)| (rel.clone(), note.title.clone()))
+        .collect();
+    let mut complete = candidates.len() == index.len();
+    drop(index);
+    candidates.sort();
+
+    let started = Instant::now();
+    let mut found = Vec::new();
+    for (path, title) in candidates {
+        if started.elapsed() > SCAN_TIME_LIMIT {
+            eprintln!("Pattern search stopped after {:?}", SCAN_TIME_LIMIT);
+            complete = false;
+            break;
+        }
+
+        let content = match fs::read_to_string(notes_dir.join(&path)) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        let body_start = content.len() - frontmatter::parse(&content).1.len();
+        let matches: Vec<Range<usize>> = pattern
+            .find_all(&content[body_start..])
+            .into_iter()
+            .map(|r| r.start + body_start..r.end + body_start)
+            .collect();
+
+        if !matches.is_empty() {
+            found.push(ScannedNote {
+                path,
+                title,
+                content,
+                matches,
+            });
+        }
+    }
+
+    (found, complete)
+}
+
+/// Byte range of the line(s) containing `range`, without the line break
+pub fn line_span(text: &str, range: &Range<usize>) -> Range<usize> {
+    let start = text[..range.start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let end = text[range.end..]
+        .find('\n')
+        .map(|i| range.end + i)
+        .unwrap_or(text.len());
+    let end = if end > range.end && text[..end].ends_with('\r') {
+        end - 1
+    } else {
+        end
+    };
+    start..end
+}
+
+/// Line number, starting at 1, of the byte at `offset`
+pub fn line_number(text: &str, offset: usize) -> usize {
+    text[..offset].matches('\n').count() + 1
+}
+
+// Results of an exact match, the notes with the most matches first
+fn pattern_results(notes: Vec<ScannedNote>, pattern: &Pattern, limit: usize) -> Vec<SearchResult> {
+    let mut results: Vec<SearchResult> = notes
+        .into_iter()
+        .map(|note| {
+            let title_highlights = pattern.find_all(&note.title);
+            let score = note.matches.len() as f32 + title_highlights.len() as f32 * TITLE_BOOST;
+
+            // One snippet per line with a match; a match running over several
+            // lines is highlighted up to the end of its first
+            let mut lines: Vec<(Range<usize>, Vec<Range<usize>>)> = Vec::new();
+            for m in &note.matches {
+                let full = lines.len() == SNIPPETS_PER_NOTE;
+                match lines.last_mut() {
+                    Some((line, highlights)) if m.start < line.end => {
+                        highlights.push(m.start - line.start..m.end.min(line.end) - line.start);
+                    }
+                    _ if full => break,
+                    _ => {
+                        let line = line_span(&note.content, &(m.start..m.start));
+                        let highlight = m.start - line.start..m.end.min(line.end) - line.start;
+                        lines.push((line, vec![highlight]));
+                    }
+                }
+            }
+
+            let snippets = lines
+                .into_iter()
+                .map(|(line, highlights)| {
+                    let (text, highlights) = window(&note.content[line.clone()], highlights);
+                    SearchSnippet {
+                        line_number: line_number(&note.content, line.start),
+                        text,
+                        highlights,
+                    }
+                })
+                .collect();
+
+            SearchResult {
+                path: note.path,
+                title: note.title,
+                score,
+                title_highlights,
+                snippets,
+            }
+        })
+        .collect();
+
+    results.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+    results.truncate(limit);
+    results
+}
+
+/// Ranked full-text search. Words are matched on their stem, `"quoted text"`
+/// as a phrase and `word*` as a prefix; filters like `tag:` and `before:`
+/// narrow the results. See `query.rs` for the syntax.
+///
+/// With `options` asking for case-sensitive, whole-word or regex matching,
+/// `query` is instead a pattern matched exactly against the note bodies,
+/// without the query syntax.
+#[tauri::command]
+pub fn search_notes(
+    query: String,
+    limit: Option<usize>,
+    options: Option<MatchOptions>,
+    state: State<AppState>,
+) -> Result<SearchResults, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    let empty = SearchResults {
+        results: Vec::new(),
+        complete: true,
+    };
+
+    let options = options.unwrap_or_default();
+    if !options.is_default() {
+        if query.is_empty() {
+            return Ok(empty);
+        }
+        let pattern = Pattern::new(&query, options)?;
+        let (notes, complete) = scan(&state, &pattern);
+        return Ok(SearchResults {
+            results: pattern_results(notes, &pattern, limit),
+            complete,
+        });
+    }
+
+    let query = query::parse(&query)?;
+    if query == query::Query::And(Vec::new()) {
+        return Ok(empty);
+    }
+
+    let mut search = state.search.lock().unwrap();
+    if search.as_ref().map(|s| s.notes_dir != notes_dir).unwrap_or(true) {
+        // Release the old writer's lock before opening another index
+        *search = None;
+        *search = Some(SearchIndex::open(&notes_dir)?);
+    }
+    let search = search.as_mut().unwrap();
+
+    // Only the plan needs the note index; a first build of a large vault
+    // shouldn't hold up every other command
+    let plan = search.plan(&lock_index(&state));
+    search.sync(plan)?;
+    Ok(SearchResults {
+        results: search.search(&query, limit)?,
+        complete: true,
+    })
+}
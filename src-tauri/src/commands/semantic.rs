@@ -0,0 +1,533 @@
+use crate::atomic;
+use crate::commands::index::lock_index;
+use crate::commands::notes::meta_dir;
+use crate::commands::settings::load_settings;
+use crate::commands::whisper::{download_file, get_models_dir};
+use crate::frontmatter;
+use crate::AppState;
+use candle_core::{DType, Device, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::bert::{BertModel, Config};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokenizers::{PaddingParams, PaddingStrategy, Tokenizer, TruncationParams};
+
+// Semantic search: notes are cut into chunks of a few paragraphs, each chunk is
+// embedded with a small sentence-transformer run on the CPU, and a query finds
+// the notes with the chunks nearest to its own embedding. It is off until an
+// embedding model is chosen in the settings.
+//
+// Models are downloaded like the Whisper models, to <models dir>/embeddings/<id>.
+// The vectors live in <notes_dir>/.onote/semantic/<model id>.bin, one file per
+// model since vectors of different models don't compare, and are brought up to
+// date with the note index before every search.
+
+// Bump when `NoteVectors` or the chunking changes
+const VECTORS_VERSION: u32 = 1;
+const DEFAULT_LIMIT: usize = 20;
+// Chunks end at the first paragraph break after this many words
+const CHUNK_WORDS: usize = 120;
+// or are cut anywhere after this many
+const MAX_CHUNK_WORDS: usize = 200;
+// Tokens seen by the model; the rest of a longer chunk is ignored
+const MAX_TOKENS: usize = 256;
+const BATCH_SIZE: usize = 16;
+// Vectors are saved after every so many notes embedded, so an interrupted
+// sync doesn't start over
+const SAVE_EVERY: usize = 50;
+const EXCERPT_CHARS: usize = 200;
+
+// Files every model directory holds
+const MODEL_FILES: &[&str] = &["config.json", "tokenizer.json", "model.safetensors"];
+
+// BERT-style sentence-transformers with mean pooling: id, Hugging Face repo,
+// size in MB, description
+const EMBEDDING_MODELS: &[(&str, &str, u32, &str)] = &[
+    (
+        "all-MiniLM-L6-v2",
+        "sentence-transformers/all-MiniLM-L6-v2",
+        90,
+        "Fast, English only",
+    ),
+    (
+        "paraphrase-multilingual-MiniLM-L12-v2",
+        "sentence-transformers/paraphrase-multilingual-MiniLM-L12-v2",
+        470,
+        "Slower, multilingual",
+    ),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingModel {
+    pub id: String,
+    pub name: String,
+    pub size_mb: u32,
+    pub description: String,
+    pub downloaded: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SemanticResult {
+    pub path: String,
+    pub title: String,
+    /// Cosine similarity of the best matching chunk, up to 1
+    pub score: f32,
+    /// Line where that chunk starts, counting from 1
+    pub line_number: usize,
+    pub excerpt: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SemanticIndexProgress {
+    pub current: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Chunk {
+    line_number: usize,
+    excerpt: String,
+    vector: Vec<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct NoteVectors {
+    modified: SystemTime,
+    size: u64,
+    title: String,
+    chunks: Vec<Chunk>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VectorFile {
+    version: u32,
+    notes: HashMap<String, NoteVectors>,
+}
+
+fn model_dir(model_id: &str) -> PathBuf {
+    get_models_dir().join("embeddings").join(model_id)
+}
+
+fn is_downloaded(model_id: &str) -> bool {
+    MODEL_FILES.iter().all(|file| model_dir(model_id).join(file).exists())
+}
+
+fn vectors_path(notes_dir: &Path, model_id: &str) -> PathBuf {
+    meta_dir(notes_dir).join("semantic").join(format!("{}.bin", model_id))
+}
+
+// A sentence-transformer loaded for inference on the CPU
+struct Embedder {
+    model: BertModel,
+    tokenizer: Tokenizer,
+}
+
+impl Embedder {
+    fn load(model_id: &str) -> Result<Self, String> {
+        let dir = model_dir(model_id);
+        if !is_downloaded(model_id) {
+            return Err(format!("Embedding model {} is not downloaded", model_id));
+        }
+
+        let config = fs::read_to_string(dir.join("config.json")).map_err(|e| e.to_string())?;
+        let config: Config = serde_json::from_str(&config).map_err(|e| e.to_string())?;
+        let weights = fs::read(dir.join("model.safetensors")).map_err(|e| e.to_string())?;
+        let vb = VarBuilder::from_buffered_safetensors(weights, DType::F32, &Device::Cpu)
+            .map_err(|e| e.to_string())?;
+        let model = BertModel::load(vb, &config).map_err(|e| e.to_string())?;
+
+        let mut tokenizer = Tokenizer::from_file(dir.join("tokenizer.json")).map_err(|e| e.to_string())?;
+        let pad_id = tokenizer.token_to_id("[PAD]").or_else(|| tokenizer.token_to_id("<pad>")).unwrap_or(0);
+        tokenizer
+            .with_padding(Some(PaddingParams {
+                strategy: PaddingStrategy::BatchLongest,
+                pad_id,
+                ..Default::default()
+            }))
+            .with_truncation(Some(TruncationParams {
+                max_length: MAX_TOKENS,
+                ..Default::default()
+            }))
+            .map_err(|e| e.to_string())?;
+
+        println!("Loaded embedding model {}", model_id);
+        Ok(Embedder { model, tokenizer })
+    }
+
+    /// Unit-length embeddings of `texts`, the mean of their token vectors
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(BATCH_SIZE) {
+            vectors.extend(self.embed_batch(batch).map_err(|e| e.to_string())?);
+        }
+        Ok(vectors)
+    }
+
+    fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, Box<dyn std::error::Error + Send + Sync>> {
+        let encodings = self.tokenizer.encode_batch(texts.to_vec(), true)?;
+        let rows = encodings.len();
+        let len = encodings.first().map(|e| e.len()).unwrap_or(0);
+        let ids: Vec<u32> = encodings.iter().flat_map(|e| e.get_ids().to_vec()).collect();
+        let mask: Vec<u32> = encodings.iter().flat_map(|e| e.get_attention_mask().to_vec()).collect();
+
+        let device = &self.model.device;
+        let ids = Tensor::from_vec(ids, (rows, len), device)?;
+        let mask = Tensor::from_vec(mask, (rows, len), device)?;
+        let output = self.model.forward(&ids, &ids.zeros_like()?, Some(&mask))?;
+
+        // Average over the real tokens, leaving out padding
+        let mask = mask.to_dtype(DType::F32)?.unsqueeze(2)?;
+        let sum = output.broadcast_mul(&mask)?.sum(1)?;
+        let mean = sum.broadcast_div(&mask.sum(1)?)?;
+        let norm = mean.sqr()?.sum_keepdim(1)?.sqrt()?;
+        Ok(mean.broadcast_div(&norm)?.to_vec2::<f32>()?)
+    }
+}
+
+// Paragraphs of the body grouped into chunks: the line each starts on, and its text
+fn chunks(content: &str) -> Vec<(usize, String)> {
+    let body = frontmatter::parse(content).1;
+    let body_line = content[..content.len() - body.len()].lines().count();
+
+    let mut chunks = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut start = 0;
+    let mut words = 0;
+
+    for (i, line) in body.lines().enumerate() {
+        let blank = line.trim().is_empty();
+        if !current.is_empty() && ((blank && words >= CHUNK_WORDS) || words >= MAX_CHUNK_WORDS) {
+            chunks.push((body_line + start + 1, current.join("\n")));
+            current.clear();
+            words = 0;
+        }
+        if blank && current.is_empty() {
+            continue;
+        }
+        if current.is_empty() {
+            start = i;
+        }
+        current.push(line);
+        words += line.split_whitespace().count();
+    }
+    if words > 0 {
+        chunks.push((body_line + start + 1, current.join("\n")));
+    }
+
+    chunks
+}
+
+fn excerpt(text: &str) -> String {
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match flat.char_indices().nth(EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}...", &flat[..end]),
+        None => flat,
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+// Shared as an `Arc`: a sync embeds with its own handle, so searches and model
+// changes don't wait for it to finish
+pub struct SemanticIndex {
+    notes_dir: PathBuf,
+    model_id: String,
+    embedder: Embedder,
+    notes: Mutex<HashMap<String, NoteVectors>>,
+    // One sync at a time
+    syncing: Mutex<()>,
+}
+
+impl SemanticIndex {
+    fn open(notes_dir: &Path, model_id: &str) -> Result<Self, String> {
+        let embedder = Embedder::load(model_id)?;
+        let notes = fs::read(vectors_path(notes_dir, model_id))
+            .ok()
+            .and_then(|bytes| bincode::deserialize::<VectorFile>(&bytes).ok())
+            .filter(|file| file.version == VECTORS_VERSION)
+            .map(|file| file.notes)
+            .unwrap_or_default();
+
+        Ok(SemanticIndex {
+            notes_dir: notes_dir.to_path_buf(),
+            model_id: model_id.to_string(),
+            embedder,
+            notes: Mutex::new(notes),
+            syncing: Mutex::new(()),
+        })
+    }
+
+    /// Embed notes that changed since they were embedded and drop notes that no
+    /// longer exist. Progress is saved as it goes, and on failure.
+    fn sync(&self, app: &AppHandle) -> Result<(), String> {
+        let _syncing = self.syncing.lock().unwrap();
+
+        let state = app.state::<AppState>();
+        let (changed, removed) = {
+            let index = lock_index(&state);
+            let mut notes = self.notes.lock().unwrap();
+            let changed: Vec<(String, SystemTime, u64, String)> = index
+                .iter()
+                .filter(|(rel, note)| {
+                    notes
+                        .get(*rel)
+                        .map(|v| v.modified != note.modified || v.size != note.size)
+                        .unwrap_or(true)
+                })
+                .map(|(rel, note)| (rel.clone(), note.modified, note.size, note.title.clone()))
+                .collect();
+            let before = notes.len();
+            notes.retain(|rel, _| index.get(rel).is_some());
+            (changed, before - notes.len())
+        };
+
+        let total = changed.len();
+        for (i, (rel, modified, size, title)) in changed.into_iter().enumerate() {
+            app.emit("semantic-index-progress", SemanticIndexProgress { current: i + 1, total })
+                .ok();
+
+            let content = match fs::read_to_string(self.notes_dir.join(&rel)) {
+                Ok(content) => content,
+                Err(_) => continue,
+            };
+            let chunks = chunks(&content);
+            // The title gives every chunk some context
+            let texts: Vec<String> = chunks.iter().map(|(_, text)| format!("{}\n{}", title, text)).collect();
+            let vectors = match self.embedder.embed(&texts) {
+                Ok(vectors) => vectors,
+                Err(e) => {
+                    // Keep the notes embedded so far for the next try
+                    if i > 0 || removed > 0 {
+                        self.save().ok();
+                    }
+                    return Err(e);
+                }
+            };
+
+            let chunks = chunks
+                .into_iter()
+                .zip(vectors)
+                .map(|((line_number, text), vector)| Chunk {
+                    line_number,
+                    excerpt: excerpt(&text),
+                    vector,
+                })
+                .collect();
+            self.notes.lock().unwrap().insert(
+                rel,
+                NoteVectors {
+                    modified,
+                    size,
+                    title,
+                    chunks,
+                },
+            );
+
+            if (i + 1) % SAVE_EVERY == 0 {
+                if let Err(e) = self.save() {
+                    eprintln!("Failed to save semantic index: {}", e);
+                }
+            }
+        }
+
+        if total > 0 || removed > 0 {
+            self.save()?;
+            println!("Semantic index updated ({} notes embedded)", total);
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let path = vectors_path(&self.notes_dir, &self.model_id);
+        let bytes = {
+            let mut notes = self.notes.lock().unwrap();
+            let file = VectorFile {
+                version: VECTORS_VERSION,
+                notes: std::mem::take(&mut *notes),
+            };
+            let bytes = bincode::serialize(&file).map_err(|e| e.to_string());
+            *notes = file.notes;
+            bytes?
+        };
+        fs::create_dir_all(path.parent().unwrap()).map_err(|e| e.to_string())?;
+        atomic::write(&path, bytes).map_err(|e| e.to_string())
+    }
+
+    fn search(&self, query: &str, limit: usize) -> Result<Vec<SemanticResult>, String> {
+        let query = self.embedder.embed(&[query.to_string()])?.remove(0);
+
+        let mut results: Vec<SemanticResult> = self
+            .notes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(rel, note)| {
+                let best = note
+                    .chunks
+                    .iter()
+                    .map(|chunk| (dot(&query, &chunk.vector), chunk))
+                    .max_by(|a, b| a.0.total_cmp(&b.0))?;
+                Some(SemanticResult {
+                    path: rel.clone(),
+                    title: note.title.clone(),
+                    score: best.0,
+                    line_number: best.1.line_number,
+                    excerpt: best.1.excerpt.clone(),
+                })
+            })
+            .collect();
+
+        results.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+        results.truncate(limit);
+        Ok(results)
+    }
+}
+
+#[tauri::command]
+pub fn list_embedding_models() -> Vec<EmbeddingModel> {
+    EMBEDDING_MODELS
+        .iter()
+        .map(|(id, _, size, desc)| EmbeddingModel {
+            id: id.to_string(),
+            name: id.to_string(),
+            size_mb: *size,
+            description: desc.to_string(),
+            downloaded: is_downloaded(id),
+        })
+        .collect()
+}
+
+/// Fetch an embedding model's files, emitting `embedding-download-progress`
+#[tauri::command]
+pub async fn download_embedding_model(model_id: String, app: AppHandle) -> Result<(), String> {
+    let (_, repo, _, _) = EMBEDDING_MODELS
+        .iter()
+        .find(|(id, _, _, _)| *id == model_id)
+        .ok_or_else(|| format!("Unknown embedding model: {}", model_id))?;
+
+    let dir = model_dir(&model_id);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    for file in MODEL_FILES {
+        let path = dir.join(file);
+        if path.exists() {
+            continue;
+        }
+        let url = format!("https://huggingface.co/{}/resolve/main/{}", repo, file);
+        println!("Downloading {} from {}", file, url);
+        download_file(&app, "embedding-download-progress", &model_id, &url, &path).await?;
+    }
+
+    println!("Embedding model downloaded to: {:?}", dir);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_embedding_model(model_id: String, state: State<AppState>) -> Result<(), String> {
+    if !EMBEDDING_MODELS.iter().any(|(id, ..)| *id == model_id) {
+        return Err(format!("Unknown embedding model: {}", model_id));
+    }
+
+    let mut semantic = state.semantic.lock().unwrap();
+    if semantic.as_ref().map(|s| s.model_id == model_id).unwrap_or(false) {
+        *semantic = None;
+    }
+
+    let dir = model_dir(&model_id);
+    if dir.exists() {
+        fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Notes nearest in meaning to `query`, best first, embedding any notes that
+/// changed since the last search first. Needs an embedding model set in the
+/// settings and downloaded.
+#[tauri::command]
+pub async fn semantic_search(query: String, limit: Option<usize>, app: AppHandle) -> Result<Vec<SemanticResult>, String> {
+    let model_id = load_settings()
+        .semantic_model
+        .ok_or("Semantic search is off; choose an embedding model in the settings")?;
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Embedding is heavy work, keep it off the async runtime
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let notes_dir = state.notes_dir.lock().unwrap().clone();
+
+        let current = state
+            .semantic
+            .lock()
+            .unwrap()
+            .clone()
+            .filter(|s| s.notes_dir == notes_dir && s.model_id == model_id);
+        let semantic = match current {
+            Some(semantic) => semantic,
+            None => {
+                // Loaded without holding the lock, which the other commands need
+                let semantic = Arc::new(SemanticIndex::open(&notes_dir, &model_id)?);
+                *state.semantic.lock().unwrap() = Some(semantic.clone());
+                semantic
+            }
+        };
+
+        semantic.sync(&app)?;
+        semantic.search(&query, limit.unwrap_or(DEFAULT_LIMIT))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(n: usize, word: &str) -> String {
+        vec![word; n].join(" ")
+    }
+
+    #[test]
+    fn chunk_lines_count_from_the_start_of_the_file() {
+        let content = "---\ntitle: Plan\n---\n\n\nFirst line\nsecond line\n";
+        assert_eq!(chunks(content), [(6, "First line\nsecond line".to_string())]);
+
+        assert_eq!(chunks("\n\nPlain note"), [(3, "Plain note".to_string())]);
+    }
+
+    #[test]
+    fn paragraphs_split_once_a_chunk_is_long_enough() {
+        // Short paragraphs stay together
+        let short = format!("{}\n\n{}\n", words(10, "a"), words(10, "b"));
+        assert_eq!(chunks(&short).len(), 1);
+
+        let content = format!("{}\n\n\n{}\n\n{}", words(CHUNK_WORDS, "a"), words(50, "b"), words(50, "c"));
+        let chunks = chunks(&content);
+        assert_eq!(chunks.iter().map(|(line, _)| *line).collect::<Vec<_>>(), [1, 4]);
+        assert_eq!(chunks[0].1, words(CHUNK_WORDS, "a"));
+        assert!(chunks[1].1.starts_with('b') && chunks[1].1.ends_with('c'));
+    }
+
+    #[test]
+    fn long_paragraphs_are_cut_without_a_blank_line() {
+        let line = words(50, "w");
+        let content = [line.as_str(); 5].join("\n");
+        let chunks = chunks(&content);
+        assert_eq!(chunks.iter().map(|(line, _)| *line).collect::<Vec<_>>(), [1, 5]);
+        assert_eq!(chunks[0].1.split_whitespace().count(), MAX_CHUNK_WORDS);
+    }
+
+    #[test]
+    fn empty_notes_have_no_chunks() {
+        assert!(chunks("").is_empty());
+        assert!(chunks("---\ntitle: Empty\n---\n\n  \n").is_empty());
+    }
+}
@@ -1,14 +1,85 @@
+use crate::atomic;
+use crate::watcher;
 use crate::AppState;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
-use tauri::State;
+use tauri::{AppHandle, State};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppSettings {
     pub notes_dir: String,
     pub model: String,
     pub font_size: u32,
+    #[serde(default)]
+    pub audio_retention: AudioRetention,
+    #[serde(default)]
+    pub audio_format: AudioFormat,
+    /// Size limit for cached transcripts; 0 disables the cache
+    #[serde(default = "default_transcript_cache_mb")]
+    pub transcript_cache_mb: u64,
+    /// Days before trashed notes are deleted for good; 0 keeps them forever
+    #[serde(default = "default_trash_retention_days")]
+    pub trash_retention_days: u32,
+    #[serde(default)]
+    pub conflict_strategy: ConflictStrategy,
+    /// Embedding model for semantic search; semantic search is off when unset
+    #[serde(default)]
+    pub semantic_model: Option<String>,
+    /// Rename notes on save to follow their first-line title
+    #[serde(default)]
+    pub sync_filename_to_title: bool,
+}
+
+// What `save_note` does when the file changed on disk since the editor read it
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// Fail the save and hand both versions to the editor
+    #[default]
+    Refuse,
+    /// Keep the file on disk and save to a `note (conflict).md` sibling
+    ConflictCopy,
+}
+
+fn default_transcript_cache_mb() -> u64 {
+    50
+}
+
+fn default_trash_retention_days() -> u32 {
+    30
+}
+
+// Storage format for new recordings. FLAC is lossless, Opus is much smaller.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioFormat {
+    #[default]
+    Wav,
+    Flac,
+    Opus,
+}
+
+// What happens to recordings in <notes_dir>/.audio once they exist
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionMode {
+    #[default]
+    KeepAll,
+    DeleteAfterTranscription,
+    Limited,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioRetention {
+    pub mode: RetentionMode,
+    /// Limited mode: delete recordings older than this many days
+    pub max_age_days: Option<u32>,
+    /// Limited mode: delete the oldest recordings once the folder exceeds this size
+    pub max_size_gb: Option<f64>,
+    /// Delete recordings that no note links to
+    pub remove_orphans: bool,
 }
 
 impl Default for AppSettings {
@@ -21,6 +92,13 @@ impl Default for AppSettings {
             notes_dir: default_dir.to_string_lossy().to_string(),
             model: "small.en".to_string(),
             font_size: 16,
+            audio_retention: AudioRetention::default(),
+            audio_format: AudioFormat::default(),
+            transcript_cache_mb: default_transcript_cache_mb(),
+            trash_retention_days: default_trash_retention_days(),
+            conflict_strategy: ConflictStrategy::default(),
+            semantic_model: None,
+            sync_filename_to_title: false,
         }
     }
 }
@@ -32,6 +110,11 @@ fn config_path() -> PathBuf {
         .join("config.json")
 }
 
+/// Load saved settings, falling back to defaults if the config is missing or unreadable
+pub fn load_settings() -> AppSettings {
+    get_settings().unwrap_or_default()
+}
+
 #[tauri::command]
 pub fn get_settings() -> Result<AppSettings, String> {
     let path = config_path();
@@ -45,7 +128,7 @@ pub fn get_settings() -> Result<AppSettings, String> {
 }
 
 #[tauri::command]
-pub fn save_settings(settings: AppSettings, state: State<AppState>) -> Result<(), String> {
+pub fn save_settings(settings: AppSettings, app: AppHandle, state: State<AppState>) -> Result<(), String> {
     let path = config_path();
 
     // Ensure config directory exists
@@ -54,14 +137,28 @@ pub fn save_settings(settings: AppSettings, state: State<AppState>) -> Result<()
     }
 
     // Update app state with new notes directory
-    {
+    let dir_changed = {
         let mut notes_dir = state.notes_dir.lock().unwrap();
-        *notes_dir = PathBuf::from(&settings.notes_dir);
-    }
+        let new_dir = PathBuf::from(&settings.notes_dir);
+        let changed = *notes_dir != new_dir;
+        *notes_dir = new_dir;
+        changed
+    };
 
     // Ensure notes directory exists
     fs::create_dir_all(&settings.notes_dir).map_err(|e| e.to_string())?;
 
+    // Point the watcher at the new directory
+    if dir_changed {
+        let mut watcher = state.watcher.lock().unwrap();
+        // Stop the old watcher before starting the new one
+        *watcher = None;
+        match watcher::start(&app, PathBuf::from(&settings.notes_dir)) {
+            Ok(w) => *watcher = Some(w),
+            Err(e) => eprintln!("Failed to watch notes directory: {}", e),
+        }
+    }
+
     let content = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
-    fs::write(&path, content).map_err(|e| e.to_string())
+    atomic::write(&path, content).map_err(|e| e.to_string())
 }
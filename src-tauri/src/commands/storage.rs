@@ -0,0 +1,395 @@
+use crate::commands::audio::{audio_dir, RECORDING_EXTENSIONS};
+use crate::commands::notes::note_files;
+use crate::commands::settings::{load_settings, AudioRetention, RetentionMode};
+use crate::AppState;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tauri::State;
+
+// Recordings younger than this are never removed by the retention policy - they
+// may still be waiting for transcription to finish and land in a note
+const ORPHAN_GRACE_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingInfo {
+    pub file_name: String,
+    pub path: String,
+    pub size_bytes: u64,
+    pub modified: String,
+    pub referenced: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StorageReport {
+    pub audio_dir: String,
+    pub total_bytes: u64,
+    pub recording_count: usize,
+    pub orphan_count: usize,
+    pub orphan_bytes: u64,
+    pub recordings: Vec<RecordingInfo>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CleanupResult {
+    pub deleted: Vec<String>,
+    pub freed_bytes: u64,
+}
+
+struct Recording {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+impl Recording {
+    fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    fn age(&self, now: SystemTime) -> Duration {
+        now.duration_since(self.modified).unwrap_or_default()
+    }
+
+    fn is_fresh(&self, now: SystemTime) -> bool {
+        self.age(now) < ORPHAN_GRACE_PERIOD
+    }
+}
+
+// All recordings in the audio directory, oldest first
+fn list_recordings(notes_dir: &Path) -> Vec<Recording> {
+    let mut recordings = Vec::new();
+
+    if let Ok(entries) = fs::read_dir(audio_dir(notes_dir)) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+            if !RECORDING_EXTENSIONS.contains(&ext) {
+                continue;
+            }
+
+            if let Ok(metadata) = entry.metadata() {
+                if metadata.is_file() {
+                    recordings.push(Recording {
+                        path,
+                        size: metadata.len(),
+                        modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                    });
+                }
+            }
+        }
+    }
+
+    recordings.sort_by_key(|r| r.modified);
+    recordings
+}
+
+/// File names of recordings mentioned by at least one note
+fn referenced_recordings(notes_dir: &Path, recordings: &[Recording]) -> HashSet<String> {
+    let mut unseen: Vec<String> = recordings.iter().map(|r| r.file_name()).collect();
+    let mut referenced = HashSet::new();
+
+    for note in note_files(notes_dir) {
+        if unseen.is_empty() {
+            break;
+        }
+        if let Ok(content) = fs::read_to_string(&note) {
+            unseen.retain(|name| {
+                if content.contains(name.as_str()) {
+                    referenced.insert(name.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+    }
+
+    referenced
+}
+
+/// Recordings mentioned in a note's text, oldest first
+pub fn linked_recordings(notes_dir: &Path, content: &str) -> Vec<PathBuf> {
+    list_recordings(notes_dir)
+        .into_iter()
+        .filter(|r| content.contains(r.file_name().as_str()))
+        .map(|r| r.path)
+        .collect()
+}
+
+fn is_orphan(recording: &Recording, referenced: &HashSet<String>, now: SystemTime) -> bool {
+    !referenced.contains(&recording.file_name()) && !recording.is_fresh(now)
+}
+
+// Decide which recordings the policy removes. Size trimming starts with the oldest,
+// and fresh recordings are kept even over the size limit.
+fn select_for_deletion(
+    recordings: &[Recording],
+    referenced: &HashSet<String>,
+    policy: &AudioRetention,
+    now: SystemTime,
+) -> Vec<bool> {
+    let mut doomed: Vec<bool> = recordings
+        .iter()
+        .map(|r| policy.remove_orphans && is_orphan(r, referenced, now))
+        .collect();
+
+    if policy.mode != RetentionMode::Limited {
+        return doomed;
+    }
+
+    if let Some(days) = policy.max_age_days {
+        let max_age = Duration::from_secs(u64::from(days) * 24 * 60 * 60);
+        for (i, recording) in recordings.iter().enumerate() {
+            if recording.age(now) > max_age && !recording.is_fresh(now) {
+                doomed[i] = true;
+            }
+        }
+    }
+
+    if let Some(max_gb) = policy.max_size_gb {
+        let limit = (max_gb.max(0.0) * 1_000_000_000.0) as u64;
+        let mut total: u64 = recordings
+            .iter()
+            .zip(&doomed)
+            .filter(|(_, d)| !**d)
+            .map(|(r, _)| r.size)
+            .sum();
+
+        for (i, recording) in recordings.iter().enumerate() {
+            if total <= limit {
+                break;
+            }
+            if !doomed[i] && !recording.is_fresh(now) {
+                doomed[i] = true;
+                total -= recording.size;
+            }
+        }
+    }
+
+    doomed
+}
+
+fn delete_selected(recordings: &[Recording], doomed: &[bool]) -> CleanupResult {
+    let mut result = CleanupResult::default();
+
+    for (recording, _) in recordings.iter().zip(doomed).filter(|(_, d)| **d) {
+        match fs::remove_file(&recording.path) {
+            Ok(()) => {
+                result.deleted.push(recording.file_name());
+                result.freed_bytes += recording.size;
+            }
+            Err(e) => eprintln!("Failed to delete {:?}: {}", recording.path, e),
+        }
+    }
+
+    result
+}
+
+/// Apply a retention policy to the recordings of a notes directory
+pub fn apply_retention(notes_dir: &Path, policy: &AudioRetention) -> CleanupResult {
+    if policy.mode != RetentionMode::Limited && !policy.remove_orphans {
+        return CleanupResult::default();
+    }
+
+    let recordings = list_recordings(notes_dir);
+    let referenced = if policy.remove_orphans {
+        referenced_recordings(notes_dir, &recordings)
+    } else {
+        HashSet::new()
+    };
+
+    let doomed = select_for_deletion(&recordings, &referenced, policy, SystemTime::now());
+    let result = delete_selected(&recordings, &doomed);
+
+    if !result.deleted.is_empty() {
+        println!(
+            "Audio retention removed {} recordings ({} bytes)",
+            result.deleted.len(),
+            result.freed_bytes
+        );
+    }
+
+    result
+}
+
+/// Remove a recording once it has been transcribed, if the policy asks for it
+pub fn recording_transcribed(notes_dir: &Path, audio_path: &Path) {
+    if load_settings().audio_retention.mode != RetentionMode::DeleteAfterTranscription {
+        return;
+    }
+
+    // Only ever touch files we recorded ourselves
+    let in_audio_dir = match (audio_path.parent(), audio_dir(notes_dir).canonicalize()) {
+        (Some(parent), Ok(dir)) => parent.canonicalize().map(|p| p == dir).unwrap_or(false),
+        _ => false,
+    };
+
+    if in_audio_dir {
+        match fs::remove_file(audio_path) {
+            Ok(()) => println!("Deleted transcribed recording: {:?}", audio_path),
+            Err(e) => eprintln!("Failed to delete {:?}: {}", audio_path, e),
+        }
+    }
+}
+
+#[tauri::command]
+pub fn storage_report(state: State<AppState>) -> Result<StorageReport, String> {
+    use chrono::{DateTime, Local};
+
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let recordings = list_recordings(&notes_dir);
+    let referenced = referenced_recordings(&notes_dir, &recordings);
+    let now = SystemTime::now();
+
+    let mut report = StorageReport {
+        audio_dir: audio_dir(&notes_dir).to_string_lossy().to_string(),
+        total_bytes: 0,
+        recording_count: recordings.len(),
+        orphan_count: 0,
+        orphan_bytes: 0,
+        recordings: Vec::with_capacity(recordings.len()),
+    };
+
+    for recording in &recordings {
+        report.total_bytes += recording.size;
+        if is_orphan(recording, &referenced, now) {
+            report.orphan_count += 1;
+            report.orphan_bytes += recording.size;
+        }
+
+        let modified: DateTime<Local> = recording.modified.into();
+        report.recordings.push(RecordingInfo {
+            file_name: recording.file_name(),
+            path: recording.path.to_string_lossy().to_string(),
+            size_bytes: recording.size,
+            modified: modified.to_rfc3339(),
+            referenced: referenced.contains(&recording.file_name()),
+        });
+    }
+
+    // Newest first for display
+    report.recordings.reverse();
+
+    Ok(report)
+}
+
+#[tauri::command]
+pub fn apply_audio_retention(state: State<AppState>) -> Result<CleanupResult, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let policy = load_settings().audio_retention;
+    Ok(apply_retention(&notes_dir, &policy))
+}
+
+#[tauri::command]
+pub fn delete_orphaned_recordings(state: State<AppState>) -> Result<CleanupResult, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let policy = AudioRetention {
+        remove_orphans: true,
+        ..AudioRetention::default()
+    };
+    Ok(apply_retention(&notes_dir, &policy))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+    const GB: u64 = 1_000_000_000;
+
+    // Named by age in days, oldest first like `list_recordings`
+    fn recordings(now: SystemTime, files: &[(f64, u64)]) -> Vec<Recording> {
+        files
+            .iter()
+            .map(|(days, size)| Recording {
+                path: PathBuf::from(format!("{}d.wav", days)),
+                size: *size,
+                modified: now - DAY.mul_f64(*days),
+            })
+            .collect()
+    }
+
+    fn limited(max_age_days: Option<u32>, max_size_gb: Option<f64>, remove_orphans: bool) -> AudioRetention {
+        AudioRetention {
+            mode: RetentionMode::Limited,
+            max_age_days,
+            max_size_gb,
+            remove_orphans,
+        }
+    }
+
+    fn referenced(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn orphans_get_a_grace_period() {
+        let now = SystemTime::now();
+        let recordings = recordings(now, &[(3.0, 1), (2.0, 1), (0.5, 1)]);
+        let policy = AudioRetention {
+            remove_orphans: true,
+            ..Default::default()
+        };
+
+        // 2d is linked from a note, 0.5d may still be transcribing
+        let doomed = select_for_deletion(&recordings, &referenced(&["2d.wav"]), &policy, now);
+        assert_eq!(doomed, [true, false, false]);
+
+        let policy = AudioRetention::default();
+        assert_eq!(select_for_deletion(&recordings, &HashSet::new(), &policy, now), [false; 3]);
+    }
+
+    #[test]
+    fn limits_only_apply_in_limited_mode() {
+        let now = SystemTime::now();
+        let recordings = recordings(now, &[(100.0, 5 * GB), (50.0, 5 * GB)]);
+
+        for mode in [RetentionMode::KeepAll, RetentionMode::DeleteAfterTranscription] {
+            let policy = AudioRetention {
+                mode,
+                ..limited(Some(1), Some(0.1), false)
+            };
+            assert_eq!(select_for_deletion(&recordings, &HashSet::new(), &policy, now), [false, false]);
+        }
+    }
+
+    #[test]
+    fn old_recordings_go_even_when_linked() {
+        let now = SystemTime::now();
+        let recordings = recordings(now, &[(40.0, 1), (20.0, 1), (5.0, 1)]);
+        let all = referenced(&["40d.wav", "20d.wav", "5d.wav"]);
+
+        let doomed = select_for_deletion(&recordings, &all, &limited(Some(30), None, false), now);
+        assert_eq!(doomed, [true, false, false]);
+    }
+
+    #[test]
+    fn size_limit_trims_oldest_first() {
+        let now = SystemTime::now();
+        let recordings = recordings(now, &[(10.0, GB), (8.0, GB), (6.0, GB), (4.0, GB)]);
+
+        let doomed = select_for_deletion(&recordings, &HashSet::new(), &limited(None, Some(2.5), false), now);
+        assert_eq!(doomed, [true, true, false, false]);
+
+        // Recordings already going for their age count towards the limit
+        let doomed = select_for_deletion(&recordings, &HashSet::new(), &limited(Some(7), Some(2.5), false), now);
+        assert_eq!(doomed, [true, true, false, false]);
+        let doomed = select_for_deletion(&recordings, &HashSet::new(), &limited(Some(9), Some(3.0), false), now);
+        assert_eq!(doomed, [true, false, false, false]);
+    }
+
+    #[test]
+    fn fresh_recordings_survive_every_limit() {
+        let now = SystemTime::now();
+        let recordings = recordings(now, &[(3.0, GB), (0.1, 2 * GB), (0.0, GB)]);
+
+        let policy = limited(Some(0), Some(0.5), true);
+        assert_eq!(select_for_deletion(&recordings, &HashSet::new(), &policy, now), [true, false, false]);
+    }
+}
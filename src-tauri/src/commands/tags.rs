@@ -0,0 +1,83 @@
+use crate::commands::index::lock_index;
+use crate::commands::notes::NoteMeta;
+use crate::markdown;
+use crate::AppState;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use tauri::State;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagInfo {
+    /// Full tag, e.g. `project/alpha`
+    pub name: String,
+    /// Last segment, e.g. `alpha`
+    pub label: String,
+    /// Notes carrying this tag or one nested below it
+    pub count: usize,
+    pub children: Vec<TagInfo>,
+}
+
+// Nest `a`, `a/b`, `a/b/c` into a tree; parents exist even if no note uses them directly
+fn build_tree(counts: &BTreeMap<String, HashSet<&String>>, parent: Option<&str>) -> Vec<TagInfo> {
+    counts
+        .iter()
+        .filter(|(name, _)| match (parent, name.rsplit_once('/')) {
+            (None, None) => true,
+            (Some(p), Some((head, _))) => head == p,
+            _ => false,
+        })
+        .map(|(name, notes)| TagInfo {
+            name: name.clone(),
+            label: name.rsplit('/').next().unwrap_or(name).to_string(),
+            count: notes.len(),
+            children: build_tree(counts, Some(name)),
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn list_tags(state: State<AppState>) -> Result<Vec<TagInfo>, String> {
+    let index = lock_index(&state);
+
+    // Every tag and each of its ancestors, with the distinct notes below it
+    let mut counts: BTreeMap<String, HashSet<&String>> = BTreeMap::new();
+    for (path, note) in index.iter() {
+        for tag in &note.tags {
+            let mut end = 0;
+            for segment in tag.split('/') {
+                end += segment.len();
+                counts.entry(tag[..end].to_string()).or_default().insert(path);
+                end += 1;
+            }
+        }
+    }
+
+    Ok(build_tree(&counts, None))
+}
+
+/// Notes tagged with `tag`, including nested tags like `tag/sub` unless `include_nested` is false
+#[tauri::command]
+pub fn list_notes_by_tag(
+    tag: String,
+    include_nested: Option<bool>,
+    state: State<AppState>,
+) -> Result<Vec<NoteMeta>, String> {
+    let tag = markdown::normalize_tag(&tag).ok_or_else(|| format!("Invalid tag: {}", tag))?;
+    let include_nested = include_nested.unwrap_or(true);
+    let prefix = format!("{}/", tag);
+
+    let index = lock_index(&state);
+    let mut notes: Vec<NoteMeta> = index
+        .iter()
+        .filter(|(_, note)| {
+            note.tags
+                .iter()
+                .any(|t| *t == tag || (include_nested && t.starts_with(&prefix)))
+        })
+        .map(|(rel, note)| note.to_meta(rel))
+        .collect();
+
+    notes.sort_by(|a, b| b.modified.cmp(&a.modified));
+
+    Ok(notes)
+}
@@ -0,0 +1,138 @@
+use crate::atomic;
+use crate::codec;
+use crate::commands::settings::load_settings;
+use crate::commands::whisper::{TranscribeOptions, Transcript};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TranscriptCacheInfo {
+    pub entries: usize,
+    pub total_bytes: u64,
+    pub limit_bytes: u64,
+}
+
+fn get_cache_dir() -> PathBuf {
+    let cache_dir = dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("opnotes")
+        .join("transcripts");
+    fs::create_dir_all(&cache_dir).ok();
+    cache_dir
+}
+
+fn entry_path(key: &str) -> PathBuf {
+    get_cache_dir().join(format!("{}.json", key))
+}
+
+fn limit_bytes() -> u64 {
+    load_settings().transcript_cache_mb * 1_000_000
+}
+
+/// Cache key from the audio content hash, the model and a hash of the remaining options
+pub fn cache_key(audio_path: &Path, options: &TranscribeOptions) -> Result<String, String> {
+    let audio_hash = codec::hash_file(audio_path)?;
+
+    let options_json = serde_json::to_string(&(
+        options.language(),
+        options.prompt.as_deref().map(str::trim).unwrap_or(""),
+    ))
+    .map_err(|e| e.to_string())?;
+    let options_hash = format!("{:x}", Sha256::digest(options_json.as_bytes()));
+
+    // Model ids come from the webview, so keep them filename-safe
+    let model: String = options
+        .model_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' { c } else { '_' })
+        .collect();
+
+    Ok(format!("{}-{}-{}", audio_hash, model, &options_hash[..16]))
+}
+
+/// Look up a cached transcript, marking it as recently used
+pub fn get(key: &str) -> Option<Transcript> {
+    let path = entry_path(key);
+    let content = fs::read_to_string(&path).ok()?;
+    let transcript = serde_json::from_str(&content).ok()?;
+
+    // Bump mtime so eviction drops the least recently used entries first
+    if let Ok(file) = fs::File::options().write(true).open(&path) {
+        file.set_modified(SystemTime::now()).ok();
+    }
+
+    Some(transcript)
+}
+
+/// Store a transcript and trim the cache back under its size limit
+pub fn put(key: &str, transcript: &Transcript) {
+    let limit = limit_bytes();
+    if limit == 0 {
+        return;
+    }
+
+    let result = serde_json::to_string(transcript)
+        .map_err(|e| e.to_string())
+        .and_then(|content| atomic::write(&entry_path(key), content).map_err(|e| e.to_string()));
+
+    match result {
+        Ok(()) => evict_to(limit),
+        Err(e) => eprintln!("Failed to cache transcript: {}", e),
+    }
+}
+
+// Cache entries with size and last use, least recently used first
+fn list_entries() -> Vec<(PathBuf, u64, SystemTime)> {
+    let mut entries: Vec<(PathBuf, u64, SystemTime)> = fs::read_dir(get_cache_dir())
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().extension().map(|ext| ext == "json").unwrap_or(false))
+                .filter_map(|e| {
+                    let metadata = e.metadata().ok()?;
+                    let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                    Some((e.path(), metadata.len(), modified))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    entries.sort_by_key(|(_, _, modified)| *modified);
+    entries
+}
+
+fn evict_to(limit: u64) {
+    let entries = list_entries();
+    let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+
+    for (path, size, _) in entries {
+        if total <= limit {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total -= size;
+        }
+    }
+}
+
+#[tauri::command]
+pub fn transcript_cache_info() -> TranscriptCacheInfo {
+    let entries = list_entries();
+
+    TranscriptCacheInfo {
+        entries: entries.len(),
+        total_bytes: entries.iter().map(|(_, size, _)| size).sum(),
+        limit_bytes: limit_bytes(),
+    }
+}
+
+#[tauri::command]
+pub fn clear_transcript_cache() -> Result<(), String> {
+    for (path, _, _) in list_entries() {
+        fs::remove_file(&path).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
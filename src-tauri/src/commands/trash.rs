@@ -0,0 +1,341 @@
+use crate::atomic;
+use crate::commands::notes::labeled_sibling;
+use crate::commands::settings::load_settings;
+use crate::paths;
+use crate::AppState;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::State;
+use walkdir::WalkDir;
+
+// Deleted notes and folders live in <notes_dir>/.trash. Each item gets its own
+// directory <id>/ holding it under its original name, next to <id>.json
+// recording where it came from.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub id: String,
+    pub name: String,
+    pub original_path: String,
+    pub deleted_at: String,
+    pub is_folder: bool,
+    pub size_bytes: u64,
+}
+
+// Contents of <id>.json
+#[derive(Debug, Serialize, Deserialize)]
+struct TrashRecord {
+    original_path: String,
+    deleted_at: String,
+    is_folder: bool,
+}
+
+pub fn trash_dir(notes_dir: &Path) -> PathBuf {
+    notes_dir.join(".trash")
+}
+
+fn record_path(notes_dir: &Path, id: &str) -> PathBuf {
+    trash_dir(notes_dir).join(format!("{}.json", id))
+}
+
+fn size_of(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Move a note or folder into the trash instead of deleting it
+pub fn move_to_trash(notes_dir: &Path, full_path: &Path) -> Result<TrashEntry, String> {
+    let name = file_name(full_path);
+    let is_folder = full_path.is_dir();
+    let now = Local::now();
+
+    let trash = trash_dir(notes_dir);
+    fs::create_dir_all(&trash).map_err(|e| e.to_string())?;
+
+    // Timestamp ids sort by deletion time; the counter separates deletions within a second
+    let stamp = now.format("%Y%m%d-%H%M%S").to_string();
+    let mut id = stamp.clone();
+    let mut counter = 1;
+    while trash.join(&id).exists() || record_path(notes_dir, &id).exists() {
+        id = format!("{}-{}", stamp, counter);
+        counter += 1;
+    }
+
+    let record = TrashRecord {
+        original_path: paths::to_relative(notes_dir, full_path),
+        deleted_at: now.to_rfc3339(),
+        is_folder,
+    };
+    let size_bytes = size_of(full_path);
+
+    // The record goes first: an item without one can't be listed or restored
+    let content = serde_json::to_string_pretty(&record).map_err(|e| e.to_string())?;
+    atomic::write(&record_path(notes_dir, &id), content).map_err(|e| e.to_string())?;
+
+    let item_dir = trash.join(&id);
+    let moved = fs::create_dir_all(&item_dir).and_then(|_| fs::rename(full_path, item_dir.join(&name)));
+    if let Err(e) = moved {
+        fs::remove_dir(&item_dir).ok();
+        fs::remove_file(record_path(notes_dir, &id)).ok();
+        return Err(e.to_string());
+    }
+
+    println!("Moved to trash: {} ({})", record.original_path, id);
+
+    Ok(TrashEntry {
+        id,
+        name,
+        original_path: record.original_path,
+        deleted_at: record.deleted_at,
+        is_folder,
+        size_bytes,
+    })
+}
+
+fn read_entry(notes_dir: &Path, id: &str) -> Option<TrashEntry> {
+    let content = fs::read_to_string(record_path(notes_dir, id)).ok()?;
+    let record: TrashRecord = serde_json::from_str(&content).ok()?;
+
+    // The item directory holds exactly one entry, the trashed file or folder
+    let item = fs::read_dir(trash_dir(notes_dir).join(id))
+        .ok()?
+        .filter_map(|e| e.ok())
+        .next()?
+        .path();
+
+    Some(TrashEntry {
+        id: id.to_string(),
+        name: file_name(&item),
+        original_path: record.original_path,
+        deleted_at: record.deleted_at,
+        is_folder: record.is_folder,
+        size_bytes: size_of(&item),
+    })
+}
+
+// All trashed items, most recently deleted first
+fn list_entries(notes_dir: &Path) -> Vec<TrashEntry> {
+    let mut entries: Vec<TrashEntry> = fs::read_dir(trash_dir(notes_dir))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().is_dir())
+                .filter_map(|e| read_entry(notes_dir, &e.file_name().to_string_lossy()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    entries.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    entries
+}
+
+fn remove_entry(notes_dir: &Path, id: &str) -> Result<(), String> {
+    let item_dir = trash_dir(notes_dir).join(id);
+    if item_dir.exists() {
+        fs::remove_dir_all(&item_dir).map_err(|e| e.to_string())?;
+    }
+    let record = record_path(notes_dir, id);
+    if record.exists() {
+        fs::remove_file(&record).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Permanently delete trashed items older than the configured number of days
+pub fn purge_expired(notes_dir: &Path, retention_days: u32) -> usize {
+    if retention_days == 0 {
+        return 0;
+    }
+
+    let max_age = Duration::from_secs(u64::from(retention_days) * 24 * 60 * 60);
+    let now = Local::now();
+    let mut purged = 0;
+
+    for entry in list_entries(notes_dir) {
+        let expired = DateTime::parse_from_rfc3339(&entry.deleted_at)
+            .map(|deleted| {
+                now.signed_duration_since(deleted)
+                    .to_std()
+                    .map(|age| age > max_age)
+                    .unwrap_or(false)
+            })
+            .unwrap_or(false);
+
+        if expired {
+            match remove_entry(notes_dir, &entry.id) {
+                Ok(()) => purged += 1,
+                Err(e) => eprintln!("Failed to purge trash item {}: {}", entry.id, e),
+            }
+        }
+    }
+
+    if purged > 0 {
+        println!("Purged {} items from trash", purged);
+    }
+
+    purged
+}
+
+// Restore to the original path, or next to it as "name (restored)" if that is taken
+fn restore_target(original: &Path) -> PathBuf {
+    if original.exists() {
+        labeled_sibling(original, "restored")
+    } else {
+        original.to_path_buf()
+    }
+}
+
+#[tauri::command]
+pub fn list_trash(state: State<AppState>) -> Result<Vec<TrashEntry>, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    Ok(list_entries(&notes_dir))
+}
+
+fn restore_entry(notes_dir: &Path, id: &str) -> Result<PathBuf, String> {
+    let entry = read_entry(notes_dir, id).ok_or_else(|| format!("Trash item not found: {}", id))?;
+    let source = trash_dir(notes_dir).join(id).join(&entry.name);
+
+    // The record lives inside the vault, but is still re-checked like any other path
+    let original = paths::resolve_entry(notes_dir, &entry.original_path)?;
+    let target = restore_target(&original);
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::rename(&source, &target).map_err(|e| e.to_string())?;
+    remove_entry(notes_dir, id)?;
+    Ok(target)
+}
+
+/// Put a trashed item back at its original path. Returns the restored path.
+#[tauri::command]
+pub fn restore_from_trash(id: String, state: State<AppState>) -> Result<String, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let id = paths::validate_name(&id)?;
+
+    let target = restore_entry(&notes_dir, id)?;
+    let rel_path = paths::to_relative(&notes_dir, &target);
+    println!("Restored from trash: {}", rel_path);
+    Ok(rel_path)
+}
+
+/// Permanently delete everything in the trash. Returns the number of items removed.
+#[tauri::command]
+pub fn empty_trash(state: State<AppState>) -> Result<usize, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let entries = list_entries(&notes_dir);
+
+    for entry in &entries {
+        remove_entry(&notes_dir, &entry.id)?;
+    }
+
+    Ok(entries.len())
+}
+
+/// Purge items older than the `trash_retention_days` setting
+#[tauri::command]
+pub fn purge_trash(state: State<AppState>) -> Result<usize, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    Ok(purge_expired(&notes_dir, load_settings().trash_retention_days))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn trashed_notes_and_folders_come_back() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("work/sub")).unwrap();
+        fs::write(root.join("work/plan.md"), "# Plan").unwrap();
+        fs::write(root.join("work/sub/a.md"), "# A").unwrap();
+
+        let note = move_to_trash(root, &root.join("work/plan.md")).unwrap();
+        let folder = move_to_trash(root, &root.join("work/sub")).unwrap();
+        assert!(!root.join("work/plan.md").exists() && !root.join("work/sub").exists());
+        assert_eq!((note.original_path.as_str(), note.is_folder), ("work/plan.md", false));
+        assert_eq!(note.size_bytes, 6);
+
+        // Both deleted within the same second get their own id
+        let listed = list_entries(root);
+        assert_eq!(listed.len(), 2);
+        assert_ne!(note.id, folder.id);
+        assert!(listed.iter().any(|e| e.id == folder.id && e.is_folder && e.name == "sub"));
+
+        assert_eq!(restore_entry(root, &note.id).unwrap(), root.join("work/plan.md"));
+        assert_eq!(fs::read_to_string(root.join("work/plan.md")).unwrap(), "# Plan");
+        assert_eq!(restore_entry(root, &folder.id).unwrap(), root.join("work/sub"));
+        assert!(root.join("work/sub/a.md").is_file());
+
+        assert!(list_entries(root).is_empty());
+        assert_eq!(fs::read_dir(trash_dir(root)).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn restoring_into_a_removed_folder_recreates_it() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("gone")).unwrap();
+        fs::write(root.join("gone/note.md"), "x").unwrap();
+
+        let entry = move_to_trash(root, &root.join("gone/note.md")).unwrap();
+        fs::remove_dir(root.join("gone")).unwrap();
+        assert_eq!(restore_entry(root, &entry.id).unwrap(), root.join("gone/note.md"));
+    }
+
+    #[test]
+    fn restore_target_avoids_taken_paths() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+
+        assert_eq!(restore_target(&root.join("note.md")), root.join("note.md"));
+
+        fs::write(root.join("note.md"), "new").unwrap();
+        assert_eq!(restore_target(&root.join("note.md")), root.join("note (restored).md"));
+        fs::write(root.join("note (restored).md"), "").unwrap();
+        assert_eq!(restore_target(&root.join("note.md")), root.join("note (restored 2).md"));
+
+        fs::create_dir(root.join("Projects")).unwrap();
+        assert_eq!(restore_target(&root.join("Projects")), root.join("Projects (restored)"));
+    }
+
+    #[test]
+    fn restoring_over_a_new_note_keeps_both() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(root.join("note.md"), "old").unwrap();
+
+        let entry = move_to_trash(root, &root.join("note.md")).unwrap();
+        fs::write(root.join("note.md"), "new").unwrap();
+
+        assert_eq!(restore_entry(root, &entry.id).unwrap(), root.join("note (restored).md"));
+        assert_eq!(fs::read_to_string(root.join("note.md")).unwrap(), "new");
+        assert_eq!(fs::read_to_string(root.join("note (restored).md")).unwrap(), "old");
+    }
+
+    #[test]
+    fn failed_move_leaves_no_record() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+
+        assert!(move_to_trash(root, &root.join("missing.md")).is_err());
+        assert!(list_entries(root).is_empty());
+        assert_eq!(fs::read_dir(trash_dir(root)).unwrap().count(), 0);
+    }
+}
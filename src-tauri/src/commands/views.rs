@@ -0,0 +1,164 @@
+use crate::commands::index::{lock_index, IndexedNote};
+use crate::commands::notes::{sort_notes, NoteMeta, NoteSort};
+use crate::commands::order::{self, FolderOrder};
+use crate::paths;
+use crate::AppState;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tauri::State;
+
+// Collections of notes across folders, so a note can be found without
+// remembering where it went. They are built from the index and returned a page
+// at a time.
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+// How far back "recent" goes
+const RECENT_DAYS: u64 = 14;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoteView {
+    All,
+    /// Modified in the last two weeks
+    Recent,
+    /// Linked to a recording
+    Recorded,
+    /// Still titled "Untitled", or with nothing but headings in them
+    Untitled,
+}
+
+impl NoteView {
+    fn includes(&self, note: &IndexedNote, now: SystemTime) -> bool {
+        match self {
+            NoteView::All => true,
+            NoteView::Recent => now
+                .duration_since(note.modified)
+                .map(|age| age < Duration::from_secs(RECENT_DAYS * 86_400))
+                .unwrap_or(true),
+            NoteView::Recorded => note.has_audio,
+            NoteView::Untitled => {
+                note.title.trim().eq_ignore_ascii_case("untitled") || note.preview.trim().is_empty()
+            }
+        }
+    }
+
+    // Recordings become notes when they're made, so those go newest made first
+    fn default_sort(&self) -> NoteSort {
+        match self {
+            NoteView::Recorded => NoteSort::Created,
+            _ => NoteSort::Modified,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NotePage {
+    pub notes: Vec<NoteMeta>,
+    /// Notes in the whole view, across all pages
+    pub total: usize,
+    pub offset: usize,
+}
+
+/// One page of a view, over the whole vault or only `folder` and its subfolders
+#[tauri::command]
+pub fn list_view(
+    view: NoteView,
+    folder: Option<String>,
+    sort: Option<NoteSort>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    state: State<AppState>,
+) -> Result<NotePage, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    let folder_path = paths::resolve(&notes_dir, folder.as_deref().unwrap_or(""))?;
+    let rel_folder = PathBuf::from(paths::to_relative(&notes_dir, &folder_path));
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+
+    let now = SystemTime::now();
+    let mut notes: Vec<NoteMeta> = lock_index(&state)
+        .iter()
+        .filter(|(rel, note)| Path::new(rel).starts_with(&rel_folder) && view.includes(note, now))
+        .map(|(rel, note)| note.to_meta(rel))
+        .collect();
+
+    sort_notes(
+        &mut notes,
+        sort.unwrap_or_else(|| view.default_sort()),
+        &FolderOrder::default(),
+    );
+
+    let total = notes.len();
+    let mut notes: Vec<NoteMeta> = notes.into_iter().skip(offset).take(limit).collect();
+    // Pins hold within a folder, so they're shown here but don't lead
+    order::mark_pinned(&notes_dir, &mut notes);
+
+    Ok(NotePage { notes, total, offset })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::index::read_entry;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const DAY: Duration = Duration::from_secs(86_400);
+
+    fn note(content: &str) -> IndexedNote {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("note.md");
+        fs::write(&path, content).unwrap();
+        read_entry(&path).unwrap()
+    }
+
+    #[test]
+    fn recent_means_the_last_two_weeks() {
+        let now = SystemTime::now();
+        let mut n = note("# Plan\n\nText");
+
+        n.modified = now - DAY * 13;
+        assert!(NoteView::Recent.includes(&n, now));
+        n.modified = now - DAY * 15;
+        assert!(!NoteView::Recent.includes(&n, now));
+        // A clock that ran ahead still counts as recent
+        n.modified = now + DAY;
+        assert!(NoteView::Recent.includes(&n, now));
+
+        assert!(NoteView::All.includes(&n, now));
+        n.modified = now - DAY * 365;
+        assert!(NoteView::All.includes(&n, now));
+    }
+
+    #[test]
+    fn untitled_by_title_or_empty_body() {
+        let now = SystemTime::now();
+
+        assert!(NoteView::Untitled.includes(&note("# Untitled\n\nSome text"), now));
+        assert!(NoteView::Untitled.includes(&note("---\ntitle: \" UNTITLED \"\n---\nText"), now));
+        // Nothing but headings
+        assert!(NoteView::Untitled.includes(&note("# Plan\n\n## Later\n"), now));
+        assert!(NoteView::Untitled.includes(&note(""), now));
+
+        assert!(!NoteView::Untitled.includes(&note("# Plan\n\nText"), now));
+        assert!(!NoteView::Untitled.includes(&note("# Untitled plans\n\nText"), now));
+    }
+
+    #[test]
+    fn recorded_notes_link_a_recording() {
+        let now = SystemTime::now();
+
+        assert!(NoteView::Recorded.includes(&note("# Call\n\nFrom recording_20260101_120000.wav"), now));
+        assert!(NoteView::Recorded.includes(&note("---\nsource_recording: call.opus\n---\nText"), now));
+        assert!(!NoteView::Recorded.includes(&note("# Call\n\nNo audio here"), now));
+    }
+
+    #[test]
+    fn recordings_list_newest_made_first() {
+        assert_eq!(NoteView::Recorded.default_sort(), NoteSort::Created);
+        assert_eq!(NoteView::Recent.default_sort(), NoteSort::Modified);
+        assert_eq!(NoteView::All.default_sort(), NoteSort::Modified);
+        assert_eq!(NoteView::Untitled.default_sort(), NoteSort::Modified);
+    }
+}
@@ -1,12 +1,19 @@
 use serde::{Deserialize, Serialize};
 use std::fs::{self, File};
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::{AppHandle, Emitter, State};
 
 #[cfg(target_os = "macos")]
 use std::process::Command;
+#[cfg(target_os = "macos")]
+use std::sync::atomic::{AtomicU64, Ordering};
 
+use crate::codec;
+use crate::commands::{storage, transcript_cache};
+use crate::diff::{self, DiffChunk};
+use crate::frontmatter;
+use crate::paths;
 use crate::AppState;
 
 // Whisper model info
@@ -34,6 +41,69 @@ pub struct DownloadProgress {
     pub percent: f32,
 }
 
+// Model, language and prompt for one transcription run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscribeOptions {
+    pub model_id: String,
+    /// Spoken language code such as "en" or "de"; English when unset
+    pub language: Option<String>,
+    /// Initial prompt that steers vocabulary and spelling
+    pub prompt: Option<String>,
+}
+
+impl TranscribeOptions {
+    pub fn language(&self) -> String {
+        self.language
+            .as_deref()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .unwrap_or("en")
+            .to_string()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
+}
+
+// Structured result of one Whisper run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transcript {
+    pub text: String,
+    pub segments: Vec<TranscriptSegment>,
+    pub model_id: String,
+    pub language: String,
+}
+
+impl Transcript {
+    fn from_segments(segments: Vec<TranscriptSegment>, options: &TranscribeOptions) -> Self {
+        let text = segments
+            .iter()
+            .map(|s| s.text.trim())
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Self {
+            text,
+            segments,
+            model_id: options.model_id.clone(),
+            language: options.language(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetranscribeResult {
+    pub audio_path: String,
+    pub text: String,
+    /// Word-level diff from the current note text to the new transcript
+    pub diff: Option<Vec<DiffChunk>>,
+}
+
 // Available models with their Hugging Face URLs
 const MODELS: &[(&str, &str, u32, &str)] = &[
     (
@@ -60,9 +130,27 @@ const MODELS: &[(&str, &str, u32, &str)] = &[
         1500,
         "High accuracy, English only, slower",
     ),
+    (
+        "base",
+        "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin",
+        142,
+        "Fast, multilingual",
+    ),
+    (
+        "small",
+        "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.bin",
+        466,
+        "Balanced speed/accuracy, multilingual",
+    ),
+    (
+        "medium",
+        "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-medium.bin",
+        1500,
+        "High accuracy, multilingual, slower",
+    ),
 ];
 
-fn get_models_dir() -> PathBuf {
+pub fn get_models_dir() -> PathBuf {
     let data_dir = dirs::data_dir()
         .unwrap_or_else(|| PathBuf::from("."))
         .join("opnotes")
@@ -122,34 +210,25 @@ pub fn set_selected_model(model_id: String, state: State<AppState>) -> Result<()
     Ok(())
 }
 
-#[tauri::command]
-pub async fn download_model(model_id: String, app: AppHandle) -> Result<String, String> {
-    let (_, url, _, _) = MODELS
-        .iter()
-        .find(|(id, _, _, _)| *id == model_id)
-        .ok_or_else(|| format!("Unknown model: {}", model_id))?;
-
-    let path = get_model_path(&model_id);
-
-    if path.exists() {
-        return Ok(path.to_string_lossy().to_string());
-    }
-
-    println!("Downloading model {} from {}", model_id, url);
-
+/// Stream `url` to `path` through a temp file next to it, emitting
+/// `DownloadProgress` for `model_id` as `event`
+pub async fn download_file(app: &AppHandle, event: &str, model_id: &str, url: &str, path: &Path) -> Result<(), String> {
     // Download with progress
     let client = reqwest::Client::new();
     let response = client
-        .get(*url)
+        .get(url)
         .send()
         .await
+        .and_then(|r| r.error_for_status())
         .map_err(|e| e.to_string())?;
 
     let total_size = response.content_length().unwrap_or(0);
     let mut downloaded: u64 = 0;
 
     // Create temp file
-    let temp_path = path.with_extension("bin.tmp");
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
     let mut file = File::create(&temp_path).map_err(|e| e.to_string())?;
 
     // Stream the download
@@ -163,7 +242,7 @@ pub async fn download_model(model_id: String, app: AppHandle) -> Result<String,
 
         // Emit progress event
         let progress = DownloadProgress {
-            model_id: model_id.clone(),
+            model_id: model_id.to_string(),
             downloaded_bytes: downloaded,
             total_bytes: total_size,
             percent: if total_size > 0 {
@@ -173,11 +252,29 @@ pub async fn download_model(model_id: String, app: AppHandle) -> Result<String,
             },
         };
 
-        app.emit("model-download-progress", &progress).ok();
+        app.emit(event, &progress).ok();
     }
 
     // Move temp file to final location
-    fs::rename(&temp_path, &path).map_err(|e| e.to_string())?;
+    fs::rename(&temp_path, path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn download_model(model_id: String, app: AppHandle) -> Result<String, String> {
+    let (_, url, _, _) = MODELS
+        .iter()
+        .find(|(id, _, _, _)| *id == model_id)
+        .ok_or_else(|| format!("Unknown model: {}", model_id))?;
+
+    let path = get_model_path(&model_id);
+
+    if path.exists() {
+        return Ok(path.to_string_lossy().to_string());
+    }
+
+    println!("Downloading model {} from {}", model_id, url);
+
+    download_file(&app, "model-download-progress", &model_id, url, &path).await?;
 
     println!("Model downloaded to: {:?}", path);
 
@@ -186,8 +283,44 @@ pub async fn download_model(model_id: String, app: AppHandle) -> Result<String,
 
 #[tauri::command]
 pub async fn transcribe(audio_path: String, state: State<'_, AppState>) -> Result<String, String> {
-    let model_id = state.selected_model.lock().unwrap().clone();
-    let model_path = get_model_path(&model_id);
+    let options = TranscribeOptions {
+        model_id: state.selected_model.lock().unwrap().clone(),
+        language: None,
+        prompt: None,
+    };
+
+    let transcript = run_transcription(&audio_path, &options).await?;
+
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+    storage::recording_transcribed(&notes_dir, Path::new(&audio_path));
+
+    Ok(transcript.text)
+}
+
+/// Run Whisper on an audio file with the given model, language and prompt.
+/// Results are cached by audio content and options, so repeat runs are instant.
+pub async fn run_transcription(audio_path: &str, options: &TranscribeOptions) -> Result<Transcript, String> {
+    let cache_key = transcript_cache::cache_key(Path::new(audio_path), options)
+        .map_err(|e| eprintln!("Transcript cache unavailable: {}", e))
+        .ok();
+
+    if let Some(transcript) = cache_key.as_deref().and_then(transcript_cache::get) {
+        println!("Transcript cache hit for {}", audio_path);
+        return Ok(transcript);
+    }
+
+    let transcript = run_whisper(audio_path, options).await?;
+
+    if let Some(key) = &cache_key {
+        transcript_cache::put(key, &transcript);
+    }
+
+    Ok(transcript)
+}
+
+async fn run_whisper(audio_path: &str, options: &TranscribeOptions) -> Result<Transcript, String> {
+    let model_id = &options.model_id;
+    let model_path = get_model_path(model_id);
 
     if !model_path.exists() {
         return Err(format!(
@@ -199,7 +332,7 @@ pub async fn transcribe(audio_path: String, state: State<'_, AppState>) -> Resul
     // Validate model file size
     let expected_size = MODELS
         .iter()
-        .find(|(id, _, _, _)| *id == model_id)
+        .find(|(id, _, _, _)| id == model_id)
         .map(|(_, _, size_mb, _)| *size_mb as u64 * 1_000_000)
         .unwrap_or(0);
 
@@ -215,31 +348,95 @@ pub async fn transcribe(audio_path: String, state: State<'_, AppState>) -> Resul
         ));
     }
 
-    println!("Transcribing {} with model {}", audio_path, model_id);
+    // English-only models silently produce garbage for other languages
+    let language = options.language();
+    if model_id.ends_with(".en") && language != "en" {
+        return Err(format!(
+            "Model '{}' only supports English. Choose a multilingual model for '{}'.",
+            model_id, language
+        ));
+    }
+
+    println!("Transcribing {} with model {} ({})", audio_path, model_id, language);
     println!("Model path: {:?}", model_path);
 
     // Use subprocess on macOS, whisper-rs on other platforms
     #[cfg(target_os = "macos")]
     {
-        transcribe_subprocess(audio_path, model_path).await
+        transcribe_subprocess(audio_path.to_string(), model_path, options.clone()).await
     }
 
     #[cfg(not(target_os = "macos"))]
     {
-        let audio_path_owned = audio_path.clone();
-        let model_path_owned = model_path.clone();
+        let audio_path_owned = audio_path.to_string();
+        let options_owned = options.clone();
 
         tokio::task::spawn_blocking(move || {
-            transcribe_with_whisper_rs(audio_path_owned, model_path_owned)
+            transcribe_with_whisper_rs(audio_path_owned, model_path, options_owned)
         })
         .await
         .map_err(|e| format!("Task failed: {:?}", e))?
     }
 }
 
+/// Transcribe a stored recording again with different settings and diff it against the note
+#[tauri::command]
+pub async fn retranscribe(
+    audio_path: Option<String>,
+    note_path: Option<String>,
+    options: TranscribeOptions,
+    state: State<'_, AppState>,
+) -> Result<RetranscribeResult, String> {
+    let notes_dir = state.notes_dir.lock().unwrap().clone();
+
+    let note_content = match &note_path {
+        Some(path) => {
+            let full_path = paths::resolve_note(&notes_dir, path)?;
+            Some(fs::read_to_string(full_path).map_err(|e| e.to_string())?)
+        }
+        None => None,
+    };
+
+    // Fall back to the newest recording linked from the note
+    let audio_path = match audio_path {
+        Some(path) => path,
+        None => {
+            let content = note_content
+                .as_deref()
+                .ok_or_else(|| "Either a recording or a note is required".to_string())?;
+            storage::linked_recordings(&notes_dir, content)
+                .pop()
+                .map(|p| p.to_string_lossy().to_string())
+                .ok_or_else(|| "This note has no linked recording".to_string())?
+        }
+    };
+
+    if !Path::new(&audio_path).exists() {
+        return Err(format!("Recording not found: {}", audio_path));
+    }
+
+    let transcript = run_transcription(&audio_path, &options).await?;
+    // The transcript only ever stands in for the body
+    let diff = note_content.map(|content| diff::word_diff(frontmatter::parse(&content).1, &transcript.text));
+
+    Ok(RetranscribeResult {
+        audio_path,
+        text: transcript.text,
+        diff,
+    })
+}
+
+// Numbers temp WAVs, so transcriptions running side by side don't share one
+#[cfg(target_os = "macos")]
+static TEMP_WAV_COUNTER: AtomicU64 = AtomicU64::new(0);
+
 /// Transcribe using whisper-cli subprocess (macOS)
 #[cfg(target_os = "macos")]
-async fn transcribe_subprocess(audio_path: String, model_path: PathBuf) -> Result<String, String> {
+async fn transcribe_subprocess(
+    audio_path: String,
+    model_path: PathBuf,
+    options: TranscribeOptions,
+) -> Result<Transcript, String> {
     let model_path_str = model_path.to_string_lossy().to_string();
 
     // Try to find whisper-cli in common locations
@@ -259,68 +456,109 @@ async fn transcribe_subprocess(audio_path: String, model_path: PathBuf) -> Resul
 
     println!("Using whisper-cli: {}", whisper_bin);
 
+    // whisper-cli only reads WAV reliably, so compressed recordings go through a temp file
+    let is_wav = Path::new(&audio_path)
+        .extension()
+        .map(|e| e.eq_ignore_ascii_case("wav"))
+        .unwrap_or(false);
+    let temp_wav = if is_wav {
+        None
+    } else {
+        let samples = codec::decode(Path::new(&audio_path))?;
+        let n = TEMP_WAV_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let temp_path = std::env::temp_dir().join(format!("opnotes-{}-{}.wav", std::process::id(), n));
+        codec::write_wav(&temp_path, &samples)?;
+        Some(temp_path)
+    };
+    let input_path = temp_wav
+        .as_ref()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or(audio_path);
+
+    let mut args = vec![
+        "-m".to_string(), model_path_str,
+        "-f".to_string(), input_path,
+        "-l".to_string(), options.language(),
+        "-np".to_string(), // no prints (progress)
+    ];
+    if let Some(prompt) = options.prompt.clone().filter(|p| !p.trim().is_empty()) {
+        args.push("--prompt".to_string());
+        args.push(prompt);
+    }
+
     let output = tokio::task::spawn_blocking(move || {
-        Command::new(&whisper_bin)
-            .args([
-                "-m", &model_path_str,
-                "-f", &audio_path,
-                "-l", "en",
-                "-nt",  // no timestamps
-                "-np",  // no prints (progress)
-            ])
-            .output()
+        Command::new(&whisper_bin).args(&args).output()
     })
     .await
-    .map_err(|e| format!("Task failed: {:?}", e))?
-    .map_err(|e| format!("Failed to run whisper-cli: {}", e))?;
+    .map_err(|e| format!("Task failed: {:?}", e))?;
+
+    if let Some(temp_path) = temp_wav {
+        fs::remove_file(temp_path).ok();
+    }
+
+    let output = output.map_err(|e| format!("Failed to run whisper-cli: {}", e))?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         return Err(format!("whisper-cli failed: {}", stderr));
     }
 
-    // With -nt flag, whisper-cli outputs plain text (no timestamps)
+    // whisper-cli prints one "[start --> end]  text" line per segment
     let stdout = String::from_utf8_lossy(&output.stdout);
-    let result = stdout.trim().to_string();
-    println!("Transcription: {}", result);
+    let transcript = Transcript::from_segments(parse_cli_segments(&stdout), &options);
+    println!("Transcription: {}", transcript.text);
 
-    Ok(result)
+    Ok(transcript)
 }
 
-/// Transcribe using whisper-rs library (Linux/Windows)
-#[cfg(not(target_os = "macos"))]
-fn transcribe_with_whisper_rs(audio_path: String, model_path: PathBuf) -> Result<String, String> {
-    use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
-
-    // Load the audio file
-    let mut reader = hound::WavReader::open(&audio_path).map_err(|e| e.to_string())?;
-    let spec = reader.spec();
+// Parse "[00:00:01.000 --> 00:00:04.500]   text" lines from whisper-cli
+#[cfg(target_os = "macos")]
+fn parse_cli_segments(stdout: &str) -> Vec<TranscriptSegment> {
+    fn parse_timestamp(ts: &str) -> Option<i64> {
+        let mut parts = ts.trim().split(':');
+        let hours: i64 = parts.next()?.parse().ok()?;
+        let minutes: i64 = parts.next()?.parse().ok()?;
+        let seconds: f64 = parts.next()?.parse().ok()?;
+        Some((hours * 3600 + minutes * 60) * 1000 + (seconds * 1000.0).round() as i64)
+    }
 
-    if spec.sample_rate != 16000 {
-        return Err(format!(
-            "Audio must be 16kHz, got {}Hz",
-            spec.sample_rate
-        ));
+    let mut segments = Vec::new();
+
+    for line in stdout.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let parsed = line
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once(']'))
+            .and_then(|(times, text)| {
+                let (start, end) = times.split_once("-->")?;
+                Some(TranscriptSegment {
+                    start_ms: parse_timestamp(start)?,
+                    end_ms: parse_timestamp(end)?,
+                    text: text.trim().to_string(),
+                })
+            });
+
+        // Lines without timestamps are kept as untimed text
+        segments.push(parsed.unwrap_or_else(|| TranscriptSegment {
+            start_ms: 0,
+            end_ms: 0,
+            text: line.to_string(),
+        }));
     }
 
-    // Read samples as f32
-    let samples: Vec<f32> = match spec.sample_format {
-        hound::SampleFormat::Int => reader
-            .samples::<i16>()
-            .map(|s| s.unwrap_or(0) as f32 / i16::MAX as f32)
-            .collect(),
-        hound::SampleFormat::Float => reader.samples::<f32>().map(|s| s.unwrap_or(0.0)).collect(),
-    };
+    segments
+}
 
-    // Convert to mono if stereo
-    let mono_samples: Vec<f32> = if spec.channels > 1 {
-        samples
-            .chunks(spec.channels as usize)
-            .map(|chunk| chunk.iter().sum::<f32>() / chunk.len() as f32)
-            .collect()
-    } else {
-        samples
-    };
+/// Transcribe using whisper-rs library (Linux/Windows)
+#[cfg(not(target_os = "macos"))]
+fn transcribe_with_whisper_rs(
+    audio_path: String,
+    model_path: PathBuf,
+    options: TranscribeOptions,
+) -> Result<Transcript, String> {
+    use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+    // Decode the recording (WAV, FLAC or Opus) into 16kHz mono samples
+    let mono_samples = codec::decode(Path::new(&audio_path))?;
 
     let model_path_str = model_path
         .to_str()
@@ -338,8 +576,12 @@ fn transcribe_with_whisper_rs(audio_path: String, model_path: PathBuf) -> Result
 
     let mut whisper_state = ctx.create_state().map_err(|e| format!("Failed to create state: {:?}", e))?;
 
+    let language = options.language();
     let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
-    params.set_language(Some("en"));
+    params.set_language(Some(&language));
+    if let Some(prompt) = options.prompt.as_deref().filter(|p| !p.trim().is_empty()) {
+        params.set_initial_prompt(prompt);
+    }
     params.set_print_special(false);
     params.set_print_progress(false);
     params.set_print_realtime(false);
@@ -352,21 +594,25 @@ fn transcribe_with_whisper_rs(audio_path: String, model_path: PathBuf) -> Result
         .map_err(|e| format!("Transcription failed: {:?}", e))?;
 
     let num_segments = whisper_state.full_n_segments();
-    let mut text = String::new();
+    let mut segments = Vec::new();
 
     for i in 0..num_segments {
         if let Some(segment) = whisper_state.get_segment(i) {
             if let Ok(segment_text) = segment.to_str_lossy() {
-                text.push_str(&segment_text);
-                text.push(' ');
+                // Whisper timestamps are in centiseconds
+                segments.push(TranscriptSegment {
+                    start_ms: segment.start_timestamp() * 10,
+                    end_ms: segment.end_timestamp() * 10,
+                    text: segment_text.to_string(),
+                });
             }
         }
     }
 
-    let result = text.trim().to_string();
-    println!("Transcription: {}", result);
+    let transcript = Transcript::from_segments(segments, &options);
+    println!("Transcription: {}", transcript.text);
 
-    Ok(result)
+    Ok(transcript)
 }
 
 #[tauri::command]
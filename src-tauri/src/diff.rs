@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+// A run of consecutive tokens with the same change kind
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffChunk {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+fn kind(tag: ChangeTag) -> DiffKind {
+    match tag {
+        ChangeTag::Equal => DiffKind::Equal,
+        ChangeTag::Insert => DiffKind::Insert,
+        ChangeTag::Delete => DiffKind::Delete,
+    }
+}
+
+fn collect_chunks<'a>(changes: impl Iterator<Item = (ChangeTag, &'a str)>) -> Vec<DiffChunk> {
+    let mut chunks: Vec<DiffChunk> = Vec::new();
+
+    for (tag, value) in changes {
+        let kind = kind(tag);
+        match chunks.last_mut() {
+            Some(last) if last.kind == kind => last.text.push_str(value),
+            _ => chunks.push(DiffChunk {
+                kind,
+                text: value.to_string(),
+            }),
+        }
+    }
+
+    chunks
+}
+
+/// Word-level diff, whitespace included so the chunks reassemble either text
+pub fn word_diff(old: &str, new: &str) -> Vec<DiffChunk> {
+    let diff = TextDiff::from_words(old, new);
+    collect_chunks(diff.iter_all_changes().map(|c| (c.tag(), c.value())))
+}
+
+/// Line-level diff; each chunk keeps its trailing newlines
+pub fn line_diff(old: &str, new: &str) -> Vec<DiffChunk> {
+    let diff = TextDiff::from_lines(old, new);
+    collect_chunks(diff.iter_all_changes().map(|c| (c.tag(), c.value())))
+}
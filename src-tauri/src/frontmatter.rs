@@ -0,0 +1,338 @@
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashSet;
+
+// Optional YAML frontmatter at the top of a note, as used by Obsidian and most
+// static site generators:
+//
+//   ---
+//   title: Standup
+//   tags: [work, meetings]
+//   ---
+//   # Standup
+//
+// Known keys get typed fields; everything else is kept in `extra`. Rendering
+// follows the block the note already has: keys keep their order and values that
+// didn't change are written as they were, so saving a note never drops or
+// reshuffles metadata written by other tools.
+
+const KNOWN_KEYS: [&str; 5] = ["title", "created", "tags", "aliases", "source_recording"];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Frontmatter {
+    pub title: Option<String>,
+    pub created: Option<String>,
+    pub tags: Vec<String>,
+    pub aliases: Vec<String>,
+    pub source_recording: Option<String>,
+    pub extra: Mapping,
+}
+
+impl Frontmatter {
+    pub fn is_empty(&self) -> bool {
+        *self == Frontmatter::default()
+    }
+
+    fn from_mapping(mut map: Mapping) -> Self {
+        let mut take = |key: &str| map.shift_remove(key);
+
+        let title = take("title").and_then(scalar);
+        let created = take("created").and_then(scalar);
+        let tags = take("tags").map(list).unwrap_or_default();
+        let aliases = take("aliases").map(list).unwrap_or_default();
+        let source_recording = take("source_recording").and_then(scalar);
+
+        // Non-string keys can't be sent to the webview as JSON
+        let extra = map
+            .into_iter()
+            .map(|(key, value)| match key {
+                Value::String(_) => (key, value),
+                other => (Value::String(scalar(other).unwrap_or_default()), value),
+            })
+            .collect();
+
+        Frontmatter {
+            title,
+            created,
+            tags,
+            aliases,
+            source_recording,
+            extra,
+        }
+    }
+
+    // A typed field as YAML. A value already in the file is kept as written if
+    // it still reads the same, so `title: 123` stays a number and `tags: a, b`
+    // a string.
+    fn known_value(&self, key: &str, previous: Option<&Value>) -> Option<Value> {
+        let text = |field: &Option<String>| {
+            let same = previous.map(|v| scalar(v.clone()) == *field);
+            (same, field.clone().map(Value::String))
+        };
+        let items = |field: &Vec<String>| {
+            let same = previous.map(|v| list(v.clone()) == *field);
+            (same, (!field.is_empty()).then(|| strings(field)))
+        };
+
+        let (same, value) = match key {
+            "title" => text(&self.title),
+            "created" => text(&self.created),
+            "tags" => items(&self.tags),
+            "aliases" => items(&self.aliases),
+            "source_recording" => text(&self.source_recording),
+            _ => return None,
+        };
+        if same == Some(true) {
+            previous.cloned()
+        } else {
+            value
+        }
+    }
+
+    // Keys in the order of `source`, the block being replaced, then new ones
+    fn to_mapping(&self, source: Option<&Mapping>) -> Mapping {
+        let mut map = Mapping::new();
+        let mut written = HashSet::new();
+
+        for (key, value) in source.into_iter().flatten() {
+            let name = match scalar(key.clone()) {
+                Some(name) => name,
+                None => continue,
+            };
+            let value = if KNOWN_KEYS.contains(&name.as_str()) {
+                self.known_value(&name, Some(value))
+            } else {
+                self.extra.get(Value::String(name.clone())).cloned()
+            };
+            if let Some(value) = value {
+                map.insert(key.clone(), value);
+            }
+            written.insert(name);
+        }
+
+        for key in KNOWN_KEYS {
+            if !written.contains(key) {
+                if let Some(value) = self.known_value(key, None) {
+                    map.insert(Value::String(key.to_string()), value);
+                }
+            }
+        }
+        for (key, value) in &self.extra {
+            if !scalar(key.clone()).map(|name| written.contains(&name)).unwrap_or(false) {
+                map.insert(key.clone(), value.clone());
+            }
+        }
+        map
+    }
+}
+
+fn scalar(value: Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+// Lists may also be written as a single comma separated string, `tags: a, b`
+fn list(value: Value) -> Vec<String> {
+    match value {
+        Value::Sequence(items) => items.into_iter().filter_map(scalar).collect(),
+        other => scalar(other)
+            .map(|s| {
+                s.split(',')
+                    .map(|part| part.trim().to_string())
+                    .filter(|part| !part.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
+
+fn strings(items: &[String]) -> Value {
+    Value::Sequence(items.iter().cloned().map(Value::String).collect())
+}
+
+// Split off a leading `---` block. Returns the YAML source and the remaining body.
+fn split(content: &str) -> Option<(&str, &str)> {
+    let rest = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let trimmed = line.trim_end_matches(['\r', '\n']);
+        if trimmed == "---" || trimmed == "..." {
+            let yaml = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            return Some((yaml, body));
+        }
+        offset += line.len();
+    }
+
+    None
+}
+
+/// Parse a note into its frontmatter and body. Notes without frontmatter, or
+/// with a block that isn't a YAML mapping, are returned unchanged as body.
+pub fn parse(content: &str) -> (Option<Frontmatter>, &str) {
+    let (yaml, body) = match split(content) {
+        Some(parts) => parts,
+        None => return (None, content),
+    };
+
+    if yaml.trim().is_empty() {
+        return (Some(Frontmatter::default()), body);
+    }
+
+    match serde_yaml::from_str::<Value>(yaml) {
+        Ok(Value::Mapping(map)) => (Some(Frontmatter::from_mapping(map)), body),
+        _ => (None, content),
+    }
+}
+
+/// Note text for the given frontmatter and body, laid out after the block of
+/// `previous`, the note's current text. An unchanged block is kept byte for byte;
+/// one left without keys is dropped, unless it was empty to begin with.
+pub fn render(frontmatter: &Frontmatter, previous: &str, body: &str) -> Result<String, String> {
+    let source = split(previous);
+    let block = source.map(|(_, old_body)| &previous[..previous.len() - old_body.len()]);
+    let source_map = source.and_then(|(yaml, _)| match serde_yaml::from_str::<Value>(yaml) {
+        Ok(Value::Mapping(map)) => Some(map),
+        _ => None,
+    });
+
+    let mapping = frontmatter.to_mapping(source_map.as_ref());
+    if mapping.is_empty() {
+        return Ok(match (source, block) {
+            (Some((yaml, _)), Some(block)) if yaml.trim().is_empty() => format!("{}{}", block, body),
+            _ => body.to_string(),
+        });
+    }
+    if let (Some(source_map), Some(block)) = (&source_map, block) {
+        if *source_map == mapping {
+            return Ok(format!("{}{}", block, body));
+        }
+    }
+
+    let yaml = serde_yaml::to_string(&mapping).map_err(|e| e.to_string())?;
+    let block = format!("---\n{}---\n", yaml);
+    // Match the line endings of the block being replaced
+    let block = if previous.starts_with("---\r\n") {
+        block.replace('\n', "\r\n")
+    } else {
+        block
+    };
+    Ok(format!("{}{}", block, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(content: &str) -> String {
+        let (frontmatter, body) = parse(content);
+        render(&frontmatter.unwrap(), content, body).unwrap()
+    }
+
+    fn keys(content: &str) -> Vec<String> {
+        let (yaml, _) = split(content).unwrap();
+        match serde_yaml::from_str::<Value>(yaml).unwrap() {
+            Value::Mapping(map) => map.keys().filter_map(|k| scalar(k.clone())).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn unchanged_frontmatter_is_kept_byte_for_byte() {
+        let content = "---\nlayout: post  # theme\ntitle: 'Quoted'\ntags: a, b\n---\n# Body\n";
+        assert_eq!(round_trip(content), content);
+    }
+
+    #[test]
+    fn keeps_unknown_keys_and_key_order() {
+        let content = "---\nlayout: post\ntags: [a]\nweight: 3\ntitle: Old\nnested:\n  x: 1\n---\nBody\n";
+        let (frontmatter, body) = parse(content);
+        let mut frontmatter = frontmatter.unwrap();
+        frontmatter.title = Some("New".to_string());
+        frontmatter.aliases = vec!["Other".to_string()];
+
+        let rendered = render(&frontmatter, content, body).unwrap();
+        assert_eq!(keys(&rendered), ["layout", "tags", "weight", "title", "nested", "aliases"]);
+        assert!(rendered.contains("weight: 3\n"));
+        assert!(rendered.contains("title: New\n"));
+        assert!(rendered.ends_with("---\nBody\n"));
+        assert_eq!(parse(&rendered).0.unwrap().extra, frontmatter.extra);
+    }
+
+    #[test]
+    fn untouched_values_keep_their_type() {
+        let content = "---\ntitle: 123\ncreated: null\ntags: a, b\n---\nBody";
+        let (frontmatter, body) = parse(content);
+        let mut frontmatter = frontmatter.unwrap();
+        assert_eq!(frontmatter.title.as_deref(), Some("123"));
+        frontmatter.aliases = vec!["x".to_string()];
+
+        let rendered = render(&frontmatter, content, body).unwrap();
+        assert!(rendered.contains("title: 123\n"), "{}", rendered);
+        assert!(rendered.contains("created: null\n"), "{}", rendered);
+        assert!(rendered.contains("tags: a, b\n"), "{}", rendered);
+    }
+
+    #[test]
+    fn removed_keys_are_dropped() {
+        let content = "---\ntitle: A\ntags: [a]\n---\nBody";
+        let (frontmatter, body) = parse(content);
+        let mut frontmatter = frontmatter.unwrap();
+        frontmatter.tags.clear();
+        assert_eq!(render(&frontmatter, content, body).unwrap(), "---\ntitle: A\n---\nBody");
+
+        frontmatter.title = None;
+        assert_eq!(render(&frontmatter, content, body).unwrap(), "Body");
+    }
+
+    #[test]
+    fn handles_crlf() {
+        let content = "---\r\ntitle: A\r\nextra: 1\r\n---\r\nBody\r\n";
+        let (frontmatter, body) = parse(content);
+        let mut frontmatter = frontmatter.unwrap();
+        assert_eq!(frontmatter.title.as_deref(), Some("A"));
+        assert_eq!(body, "Body\r\n");
+        assert_eq!(round_trip(content), content);
+
+        frontmatter.title = Some("B".to_string());
+        assert_eq!(
+            render(&frontmatter, content, body).unwrap(),
+            "---\r\ntitle: B\r\nextra: 1\r\n---\r\nBody\r\n"
+        );
+    }
+
+    #[test]
+    fn empty_block_round_trips() {
+        let content = "---\n---\nBody";
+        let (frontmatter, body) = parse(content);
+        assert_eq!(frontmatter, Some(Frontmatter::default()));
+        assert_eq!(body, "Body");
+        assert_eq!(round_trip(content), content);
+    }
+
+    #[test]
+    fn block_that_is_not_a_mapping_stays_body() {
+        for content in ["---\n- a\n- b\n---\nBody", "---\njust text\n---\nBody", "---\ntitle: A\nno end"] {
+            assert_eq!(parse(content), (None, content));
+        }
+
+        // New frontmatter goes in front, leaving the text as it was
+        let content = "---\n- a\n---\nBody";
+        let frontmatter = Frontmatter {
+            title: Some("A".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            render(&frontmatter, content, parse(content).1).unwrap(),
+            format!("---\ntitle: A\n---\n{}", content)
+        );
+    }
+}
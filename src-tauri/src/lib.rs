@@ -1,6 +1,18 @@
+mod atomic;
+mod codec;
 mod commands;
+mod diff;
+mod frontmatter;
+mod markdown;
+mod paths;
+mod pattern;
+mod query;
+mod watcher;
 
 use commands::audio::RecordingState;
+use commands::index::NoteIndex;
+use commands::search::SearchIndex;
+use commands::semantic::SemanticIndex;
 use ringbuf::HeapCons;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
@@ -12,6 +24,10 @@ pub struct AppState {
     pub selected_model: Mutex<String>,
     pub recording: Arc<Mutex<RecordingState>>,
     pub sample_consumer: Arc<Mutex<Option<Arc<Mutex<HeapCons<f32>>>>>>,
+    pub watcher: Mutex<Option<watcher::VaultWatcher>>,
+    pub index: Mutex<NoteIndex>,
+    pub search: Mutex<Option<SearchIndex>>,
+    pub semantic: Mutex<Option<Arc<SemanticIndex>>>,
 }
 
 impl Default for AppState {
@@ -26,6 +42,10 @@ impl Default for AppState {
             selected_model: Mutex::new("small.en".to_string()),
             recording: Arc::new(Mutex::new(RecordingState::default())),
             sample_consumer: Arc::new(Mutex::new(None)),
+            watcher: Mutex::new(None),
+            index: Mutex::new(NoteIndex::default()),
+            search: Mutex::new(None),
+            semantic: Mutex::new(None),
         }
     }
 }
@@ -45,6 +65,23 @@ pub fn run() {
                 std::fs::create_dir_all(notes_dir.join("inbox")).ok();
             }
 
+            // Push changes in the notes directory to the webview
+            match watcher::start(app.handle(), notes_dir.clone()) {
+                Ok(w) => *state.watcher.lock().unwrap() = Some(w),
+                Err(e) => eprintln!("Failed to watch notes directory: {}", e),
+            }
+
+            // Apply the audio retention policy, purge old trash and catch the note
+            // index up with outside edits in the background
+            let settings = commands::settings::load_settings();
+            let handle = app.handle().clone();
+            std::thread::spawn(move || {
+                handle.state::<AppState>().index.lock().unwrap().open(&notes_dir);
+                commands::storage::apply_retention(&notes_dir, &settings.audio_retention);
+                commands::trash::purge_expired(&notes_dir, settings.trash_retention_days);
+                commands::recovery::scan(&notes_dir);
+            });
+
             // Open devtools in release builds for debugging
             #[cfg(feature = "devtools")]
             {
@@ -65,10 +102,57 @@ pub fn run() {
             commands::notes::create_folder,
             commands::notes::delete_folder,
             commands::notes::rename_folder,
-            commands::notes::search_notes,
+            commands::notes::move_folder,
+            commands::notes::merge_folders,
+            commands::notes::duplicate_folder,
+            commands::notes::move_note,
+            commands::notes::rename_note,
+            // Recovery
+            commands::recovery::list_recovery_files,
+            commands::recovery::resolve_recovery_file,
+            // History
+            commands::history::list_versions,
+            commands::history::read_version,
+            commands::history::diff_versions,
+            commands::history::restore_version,
+            // Links
+            commands::links::get_backlinks,
+            commands::links::resolve_link,
+            // Index and search
+            commands::index::rebuild_index,
+            commands::search::search_notes,
+            commands::semantic::semantic_search,
+            commands::semantic::list_embedding_models,
+            commands::semantic::download_embedding_model,
+            commands::semantic::delete_embedding_model,
+            // Replace
+            commands::replace::preview_replace,
+            commands::replace::replace_in_notes,
+            commands::replace::list_replace_backups,
+            commands::replace::undo_replace,
+            // Quick open
+            commands::quick_open::quick_open,
+            commands::quick_open::record_note_open,
+            // Order
+            commands::order::set_note_pinned,
+            commands::order::reorder_notes,
+            // Tags
+            commands::tags::list_tags,
+            commands::tags::list_notes_by_tag,
+            // Trash
+            commands::trash::list_trash,
+            commands::trash::restore_from_trash,
+            commands::trash::empty_trash,
+            commands::trash::purge_trash,
+            // Views
+            commands::views::list_view,
             // Settings
             commands::settings::get_settings,
             commands::settings::save_settings,
+            // Storage
+            commands::storage::storage_report,
+            commands::storage::apply_audio_retention,
+            commands::storage::delete_orphaned_recordings,
             // Audio
             commands::audio::list_audio_devices,
             commands::audio::get_selected_device,
@@ -84,6 +168,10 @@ pub fn run() {
             commands::whisper::set_selected_model,
             commands::whisper::download_model,
             commands::whisper::transcribe,
+            commands::whisper::retranscribe,
+            commands::batch::batch_transcribe,
+            commands::transcript_cache::transcript_cache_info,
+            commands::transcript_cache::clear_transcript_cache,
             commands::whisper::delete_model,
             commands::whisper::check_whisper_cli,
         ])
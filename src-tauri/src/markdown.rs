@@ -0,0 +1,213 @@
+use crate::frontmatter::Frontmatter;
+use std::ops::Range;
+
+// Lightweight scanning of note bodies. This is not a Markdown parser: it only
+// knows enough to skip code blocks and inline code, where `#tags` and other
+// syntax must not be picked up.
+
+pub struct ProseLine {
+    /// Line number, starting at 0
+    pub number: usize,
+    /// Byte offset of the line in the scanned text
+    pub offset: usize,
+    pub text: String,
+}
+
+/// Lines of `body` outside fenced code blocks, with inline code blanked out.
+/// Blanking keeps byte offsets intact.
+pub fn prose_lines(body: &str) -> Vec<ProseLine> {
+    let mut lines = Vec::new();
+    let mut fence: Option<&str> = None;
+    let mut offset = 0;
+
+    for (number, raw) in body.split_inclusive('\n').enumerate() {
+        let line = raw.trim_end_matches(['\r', '\n']);
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m));
+
+        match (fence, marker) {
+            (None, Some(m)) => fence = Some(m),
+            (Some(open), Some(m)) if open == m => fence = None,
+            (None, None) => lines.push(ProseLine {
+                number,
+                offset,
+                text: blank_inline_code(line),
+            }),
+            _ => {}
+        }
+        offset += raw.len();
+    }
+
+    lines
+}
+
+fn blank_inline_code(line: &str) -> String {
+    if !line.contains('`') {
+        return line.to_string();
+    }
+
+    let mut out = String::with_capacity(line.len());
+    let mut in_code = false;
+    for c in line.chars() {
+        if c == '`' {
+            in_code = !in_code;
+            out.push(' ');
+        } else if in_code {
+            // Same byte length, so offsets into the line stay valid
+            out.extend(std::iter::repeat_n(' ', c.len_utf8()));
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '/')
+}
+
+/// Normalized form of a tag: no leading `#`, no stray slashes, lowercase
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().trim_start_matches('#').trim_matches('/');
+    let valid = !tag.is_empty()
+        && tag.chars().all(is_tag_char)
+        && !tag.contains("//")
+        // `#123` is an issue number, not a tag
+        && tag.chars().any(|c| !c.is_ascii_digit() && c != '/');
+
+    valid.then(|| tag.to_lowercase())
+}
+
+/// `#tag` tokens in a single line of prose
+fn line_tags(line: &str, tags: &mut Vec<String>) {
+    let mut prev: Option<char> = None;
+
+    for (i, c) in line.char_indices() {
+        let starts_tag = c == '#'
+            && prev
+                .map(|p| p.is_whitespace() || matches!(p, '(' | '[' | '{' | ',' | ';' | '"' | '\''))
+                .unwrap_or(true);
+
+        if starts_tag {
+            let rest = &line[i + 1..];
+            let end = rest.find(|c: char| !is_tag_char(c)).unwrap_or(rest.len());
+            if let Some(tag) = normalize_tag(&rest[..end]) {
+                tags.push(tag);
+            }
+        }
+        prev = Some(c);
+    }
+}
+
+/// Tags of a note from its frontmatter `tags:` and `#tag` tokens in the body,
+/// deduplicated in order of first appearance
+pub fn extract_tags(frontmatter: Option<&Frontmatter>, body: &str) -> Vec<String> {
+    let mut tags: Vec<String> = frontmatter
+        .map(|f| f.tags.iter().filter_map(|t| normalize_tag(t)).collect())
+        .unwrap_or_default();
+
+    for line in prose_lines(body) {
+        line_tags(&line.text, &mut tags);
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    tags.retain(|tag| seen.insert(tag.clone()));
+    tags
+}
+
+/// Number of unchecked `- [ ]` tasks
+pub fn open_tasks(body: &str) -> usize {
+    prose_lines(body)
+        .iter()
+        .filter(|line| {
+            let trimmed = line.text.trim_start();
+            ["- [ ]", "* [ ]", "+ [ ]"].iter().any(|m| trimmed.starts_with(m))
+        })
+        .count()
+}
+
+#[derive(Debug, Clone)]
+pub struct WikiLink {
+    /// Link target as written, without `#heading` and `|alias`
+    pub target: String,
+    /// Line number, starting at 0
+    pub line: usize,
+    /// Byte range of `target` in the scanned text, for rewriting it in place
+    pub target_range: Range<usize>,
+}
+
+/// `[[target]]`, `[[target|alias]]` and `[[target#heading]]` links, embeds included
+pub fn extract_links(body: &str) -> Vec<WikiLink> {
+    let mut links = Vec::new();
+
+    for line in prose_lines(body) {
+        let text = &line.text;
+        let mut from = 0;
+
+        while let Some(open) = text[from..].find("[[").map(|i| from + i) {
+            let inner_start = open + 2;
+            let close = match text[inner_start..].find("]]") {
+                Some(i) => inner_start + i,
+                None => break,
+            };
+            from = close + 2;
+
+            let inner = &text[inner_start..close];
+            let left = inner.split('|').next().unwrap_or(inner);
+            let target = left.split('#').next().unwrap_or(left);
+
+            // `[[#heading]]` points into the same note
+            let trimmed = target.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            let start = line.offset + inner_start + (target.len() - target.trim_start().len());
+            links.push(WikiLink {
+                target: trimmed.to_string(),
+                line: line.number,
+                target_range: start..start + trimmed.len(),
+            });
+        }
+    }
+
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_link_targets_without_alias_or_heading() {
+        let body = "See [[Note]] and [[folder/other|the other]].\n![[embed#part]] [[#local]]\n";
+        let links = extract_links(body);
+        let targets: Vec<&str> = links.iter().map(|l| l.target.as_str()).collect();
+
+        assert_eq!(targets, ["Note", "folder/other", "embed"]);
+        assert_eq!(links.iter().map(|l| l.line).collect::<Vec<_>>(), [0, 0, 1]);
+        for link in &links {
+            assert_eq!(&body[link.target_range.clone()], link.target);
+        }
+    }
+
+    #[test]
+    fn skips_links_in_code() {
+        let body = "[[a]] `[[b]]`\n```\n[[c]]\n```\n[[ d ]]\n";
+        let links = extract_links(body);
+
+        assert_eq!(links.iter().map(|l| l.target.as_str()).collect::<Vec<_>>(), ["a", "d"]);
+        assert_eq!(&body[links[1].target_range.clone()], "d");
+        assert_eq!(links[1].line, 4);
+    }
+
+    #[test]
+    fn ranges_survive_non_ascii_and_crlf() {
+        let body = "Ünïcode [[Café]]\r\n[[naïve]]\r\n";
+        let links = extract_links(body);
+
+        assert_eq!(&body[links[0].target_range.clone()], "Café");
+        assert_eq!(&body[links[1].target_range.clone()], "naïve");
+        assert_eq!(links[1].line, 1);
+    }
+}
@@ -0,0 +1,341 @@
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+// Every path that arrives from the webview is relative to the notes directory.
+// These helpers turn such strings into filesystem paths and refuse anything that
+// could reach outside the vault.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    /// Empty path where a file or folder is required
+    Empty,
+    /// Absolute path, drive prefix or UNC share
+    Absolute,
+    /// `..` component
+    ParentTraversal,
+    /// Component starting with `.`, reserved for app data like `.audio`
+    Hidden,
+    /// Resolves to the notes directory itself
+    IsRoot,
+    /// Resolves outside the notes directory, e.g. through a symlink
+    OutsideVault,
+    /// Not a single valid file or folder name
+    InvalidName(String),
+    /// Not a `.md` or `.txt` file
+    NotANote,
+    Io(String),
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::Empty => write!(f, "Path is empty"),
+            PathError::Absolute => write!(f, "Absolute paths are not allowed"),
+            PathError::ParentTraversal => write!(f, "Path may not contain '..'"),
+            PathError::Hidden => write!(f, "Hidden files and folders are not accessible"),
+            PathError::IsRoot => write!(f, "Operation not allowed on the notes folder itself"),
+            PathError::OutsideVault => write!(f, "Path is outside the notes folder"),
+            PathError::InvalidName(name) => write!(f, "Invalid name: '{}'", name),
+            PathError::NotANote => write!(f, "Not a note file"),
+            PathError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for PathError {}
+
+impl From<PathError> for String {
+    fn from(e: PathError) -> Self {
+        e.to_string()
+    }
+}
+
+// Split on both separators so Windows-style input is checked the same everywhere
+fn components(rel: &str) -> Result<Vec<&str>, PathError> {
+    if rel.contains('\0') {
+        return Err(PathError::InvalidName(rel.replace('\0', "\\0")));
+    }
+
+    let path = Path::new(rel);
+    if path.is_absolute()
+        || path.has_root()
+        || rel.starts_with('\\')
+        || path.components().any(|c| matches!(c, Component::Prefix(_)))
+    {
+        return Err(PathError::Absolute);
+    }
+
+    let mut parts = Vec::new();
+    for part in rel.split(['/', '\\']) {
+        match part {
+            "" | "." => continue,
+            ".." => return Err(PathError::ParentTraversal),
+            p if p.trim().is_empty() => return Err(PathError::InvalidName(rel.to_string())),
+            p if p.starts_with('.') => return Err(PathError::Hidden),
+            // "C:" and friends only mean something on Windows, but are never valid names
+            p if p.contains(':') => return Err(PathError::Absolute),
+            p => parts.push(p),
+        }
+    }
+
+    Ok(parts)
+}
+
+// The deepest part of `path` that exists must canonicalize to somewhere inside `root`.
+// Checking the existing prefix catches symlinked folders and dangling symlinks alike.
+fn check_inside(root: &Path, path: &Path) -> Result<(), PathError> {
+    let canonical_root = root.canonicalize().map_err(|e| PathError::Io(e.to_string()))?;
+
+    for ancestor in path.ancestors() {
+        if fs::symlink_metadata(ancestor).is_err() {
+            continue;
+        }
+
+        // A dangling symlink fails to canonicalize; writing through it would escape
+        let canonical = ancestor
+            .canonicalize()
+            .map_err(|_| PathError::OutsideVault)?;
+
+        return if canonical.starts_with(&canonical_root) {
+            Ok(())
+        } else {
+            Err(PathError::OutsideVault)
+        };
+    }
+
+    Err(PathError::OutsideVault)
+}
+
+/// Resolve a vault-relative path. The notes directory itself is allowed (`""`).
+pub fn resolve(root: &Path, rel: &str) -> Result<PathBuf, PathError> {
+    let mut path = root.to_path_buf();
+    for part in components(rel)? {
+        path.push(part);
+    }
+
+    check_inside(root, &path)?;
+    Ok(path)
+}
+
+/// Resolve a path naming a file or folder inside the vault, never the vault root
+pub fn resolve_entry(root: &Path, rel: &str) -> Result<PathBuf, PathError> {
+    if rel.trim().is_empty() {
+        return Err(PathError::Empty);
+    }
+    if components(rel)?.is_empty() {
+        return Err(PathError::IsRoot);
+    }
+
+    let path = resolve(root, rel)?;
+
+    // Symlinks can make a non-empty path land on the root again
+    if let (Ok(a), Ok(b)) = (path.canonicalize(), root.canonicalize()) {
+        if a == b {
+            return Err(PathError::IsRoot);
+        }
+    }
+
+    Ok(path)
+}
+
+/// Resolve a path that must name a `.md` or `.txt` note
+pub fn resolve_note(root: &Path, rel: &str) -> Result<PathBuf, PathError> {
+    let path = resolve_entry(root, rel)?;
+    let is_note = path
+        .extension()
+        .map(|ext| ext == "md" || ext == "txt")
+        .unwrap_or(false);
+
+    if is_note {
+        Ok(path)
+    } else {
+        Err(PathError::NotANote)
+    }
+}
+
+/// Validate a single file or folder name, e.g. the target of a rename
+pub fn validate_name(name: &str) -> Result<&str, PathError> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() {
+        return Err(PathError::Empty);
+    }
+
+    match components(trimmed)?.as_slice() {
+        [single] if *single == trimmed => Ok(trimmed),
+        _ => Err(PathError::InvalidName(name.to_string())),
+    }
+}
+
+/// Path relative to the notes directory, as sent back to the webview
+pub fn to_relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn vault() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("inbox")).unwrap();
+        fs::write(dir.path().join("inbox/note.md"), "# Note").unwrap();
+        dir
+    }
+
+    #[test]
+    fn resolves_plain_relative_paths() {
+        let dir = vault();
+        let root = dir.path();
+
+        assert_eq!(resolve(root, "inbox").unwrap(), root.join("inbox"));
+        assert_eq!(resolve(root, "inbox/note.md").unwrap(), root.join("inbox/note.md"));
+        assert_eq!(resolve(root, "./inbox//note.md").unwrap(), root.join("inbox/note.md"));
+        assert_eq!(resolve(root, "inbox/new/deeper.md").unwrap(), root.join("inbox/new/deeper.md"));
+        assert_eq!(resolve(root, "").unwrap(), root.to_path_buf());
+    }
+
+    #[test]
+    fn rejects_parent_traversal() {
+        let dir = vault();
+        let root = dir.path();
+
+        for hostile in [
+            "..",
+            "../",
+            "../../.ssh/id_rsa",
+            "inbox/../../etc/passwd",
+            "inbox/..",
+            "..\\..\\Windows\\System32",
+            "inbox\\..\\..\\secret",
+        ] {
+            assert_eq!(resolve(root, hostile), Err(PathError::ParentTraversal), "{}", hostile);
+        }
+    }
+
+    #[test]
+    fn rejects_absolute_paths() {
+        let dir = vault();
+        let root = dir.path();
+
+        for hostile in [
+            "/etc/passwd",
+            "/",
+            "\\\\server\\share\\file.md",
+            "\\Windows",
+            "C:\\Windows\\win.ini",
+            "C:/Users",
+            "c:relative.md",
+        ] {
+            assert_eq!(resolve(root, hostile), Err(PathError::Absolute), "{}", hostile);
+        }
+
+        let absolute_inside = root.join("inbox/note.md");
+        assert_eq!(
+            resolve(root, &absolute_inside.to_string_lossy()),
+            Err(PathError::Absolute)
+        );
+    }
+
+    #[test]
+    fn rejects_hidden_components_and_nul() {
+        let dir = vault();
+        let root = dir.path();
+
+        assert_eq!(resolve(root, ".audio/recording.wav"), Err(PathError::Hidden));
+        assert_eq!(resolve(root, "inbox/.git/config"), Err(PathError::Hidden));
+        assert_eq!(resolve(root, ".onote"), Err(PathError::Hidden));
+        assert!(matches!(resolve(root, "inbox/note\0.md"), Err(PathError::InvalidName(_))));
+    }
+
+    #[test]
+    fn entry_rejects_the_root_itself() {
+        let dir = vault();
+        let root = dir.path();
+
+        assert_eq!(resolve_entry(root, ""), Err(PathError::Empty));
+        assert_eq!(resolve_entry(root, "   "), Err(PathError::Empty));
+        assert_eq!(resolve_entry(root, "."), Err(PathError::IsRoot));
+        assert_eq!(resolve_entry(root, "./"), Err(PathError::IsRoot));
+        assert_eq!(resolve_entry(root, "//"), Err(PathError::Absolute));
+        assert!(resolve_entry(root, "inbox").is_ok());
+    }
+
+    #[test]
+    fn note_paths_need_a_note_extension() {
+        let dir = vault();
+        let root = dir.path();
+
+        assert!(resolve_note(root, "inbox/note.md").is_ok());
+        assert!(resolve_note(root, "inbox/plain.txt").is_ok());
+        assert_eq!(resolve_note(root, "inbox"), Err(PathError::NotANote));
+        assert_eq!(resolve_note(root, "inbox/script.sh"), Err(PathError::NotANote));
+        assert_eq!(resolve_note(root, "inbox/note.md.exe"), Err(PathError::NotANote));
+    }
+
+    #[test]
+    fn validates_single_names() {
+        assert_eq!(validate_name("projects"), Ok("projects"));
+        assert_eq!(validate_name("  spaced name "), Ok("spaced name"));
+        assert_eq!(validate_name(""), Err(PathError::Empty));
+        assert_eq!(validate_name("."), Err(PathError::InvalidName(".".to_string())));
+        assert_eq!(validate_name(".."), Err(PathError::ParentTraversal));
+        assert_eq!(validate_name("a/b"), Err(PathError::InvalidName("a/b".to_string())));
+        assert_eq!(validate_name("a\\b"), Err(PathError::InvalidName("a\\b".to_string())));
+        assert_eq!(validate_name("/etc"), Err(PathError::Absolute));
+        assert_eq!(validate_name(".hidden"), Err(PathError::Hidden));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlink_breakouts() {
+        use std::os::unix::fs::symlink;
+
+        let dir = vault();
+        let root = dir.path();
+        let outside = TempDir::new().unwrap();
+        fs::write(outside.path().join("secret.md"), "secret").unwrap();
+
+        // Folder symlink pointing outside the vault
+        symlink(outside.path(), root.join("escape")).unwrap();
+        assert_eq!(resolve(root, "escape"), Err(PathError::OutsideVault));
+        assert_eq!(resolve(root, "escape/secret.md"), Err(PathError::OutsideVault));
+        assert_eq!(resolve(root, "escape/new.md"), Err(PathError::OutsideVault));
+
+        // File symlink pointing outside the vault
+        symlink(outside.path().join("secret.md"), root.join("inbox/link.md")).unwrap();
+        assert_eq!(resolve_note(root, "inbox/link.md"), Err(PathError::OutsideVault));
+
+        // Dangling symlink: writing through it would create a file outside
+        symlink(outside.path().join("missing.md"), root.join("inbox/dangling.md")).unwrap();
+        assert_eq!(resolve_note(root, "inbox/dangling.md"), Err(PathError::OutsideVault));
+
+        // Symlink back to the vault root
+        symlink(root, root.join("inbox/loop")).unwrap();
+        assert_eq!(resolve_entry(root, "inbox/loop"), Err(PathError::IsRoot));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn allows_symlinks_that_stay_inside() {
+        use std::os::unix::fs::symlink;
+
+        let dir = vault();
+        let root = dir.path();
+        symlink(root.join("inbox"), root.join("alias")).unwrap();
+
+        assert_eq!(resolve(root, "alias/note.md").unwrap(), root.join("alias/note.md"));
+    }
+
+    #[test]
+    fn relative_paths_are_reported_against_the_root() {
+        let dir = vault();
+        let root = dir.path();
+
+        assert_eq!(to_relative(root, &root.join("inbox/note.md")), "inbox/note.md");
+    }
+}
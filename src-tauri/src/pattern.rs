@@ -0,0 +1,229 @@
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Range;
+
+// Exact matching for search and replace, as opposed to the ranked, stemmed
+// search of the query language. Every mode compiles to a `regex::Regex`, which
+// runs in time linear in the text, so a pattern can't backtrack forever; what
+// is left to bound is the size of the compiled pattern.
+
+// Longest pattern accepted, in bytes
+const MAX_PATTERN_LEN: usize = 1000;
+// Memory the compiled program and its lazy DFA may use
+const SIZE_LIMIT: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MatchOptions {
+    pub case_sensitive: bool,
+    /// Only match where the pattern starts and ends on a word boundary
+    pub whole_word: bool,
+    /// Treat the pattern as a regular expression rather than literal text
+    pub regex: bool,
+}
+
+impl MatchOptions {
+    /// None of the options are set, so plain ranked search applies
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatternError {
+    Empty,
+    TooLong,
+    /// Not valid regex syntax, with the parser's explanation
+    Invalid(String),
+    /// Compiles to more than the size limit
+    TooComplex,
+    /// Matches the empty string, e.g. `a*`, which would match everywhere
+    MatchesEmpty,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatternError::Empty => write!(f, "Search pattern is empty"),
+            PatternError::TooLong => write!(f, "Search pattern is longer than {} characters", MAX_PATTERN_LEN),
+            PatternError::Invalid(e) => write!(f, "{}", e),
+            PatternError::TooComplex => write!(f, "Regular expression is too complex"),
+            PatternError::MatchesEmpty => write!(f, "Pattern matches empty text"),
+        }
+    }
+}
+
+impl From<PatternError> for String {
+    fn from(e: PatternError) -> Self {
+        e.to_string()
+    }
+}
+
+/// A match and the text to put in its place
+pub type Replacement = (Range<usize>, String);
+
+pub struct Pattern {
+    regex: Regex,
+    // `$1` style references in replacements only mean something for regexes
+    expand: bool,
+}
+
+impl Pattern {
+    pub fn new(pattern: &str, options: MatchOptions) -> Result<Self, PatternError> {
+        if pattern.is_empty() {
+            return Err(PatternError::Empty);
+        }
+        if pattern.len() > MAX_PATTERN_LEN {
+            return Err(PatternError::TooLong);
+        }
+
+        let source = if options.regex {
+            pattern.to_string()
+        } else {
+            regex::escape(pattern)
+        };
+        let source = if options.whole_word {
+            format!(r"\b(?:{})\b", source)
+        } else {
+            source
+        };
+
+        let regex = RegexBuilder::new(&source)
+            .case_insensitive(!options.case_sensitive)
+            .multi_line(true)
+            .crlf(true)
+            .size_limit(SIZE_LIMIT)
+            .dfa_size_limit(SIZE_LIMIT)
+            .build()
+            .map_err(|e| match e {
+                regex::Error::CompiledTooBig(_) => PatternError::TooComplex,
+                e => PatternError::Invalid(e.to_string()),
+            })?;
+
+        if regex.is_match("") {
+            return Err(PatternError::MatchesEmpty);
+        }
+
+        Ok(Pattern {
+            regex,
+            expand: options.regex,
+        })
+    }
+
+    /// Byte ranges of every match in `text`
+    pub fn find_all(&self, text: &str) -> Vec<Range<usize>> {
+        self.regex.find_iter(text).map(|m| m.range()).collect()
+    }
+
+    /// Every match in `text` with what it would be replaced by. Regexes may
+    /// refer to groups in `replacement` as `$1` or `${name}`.
+    pub fn replacements(&self, text: &str, replacement: &str) -> Vec<Replacement> {
+        self.regex
+            .captures_iter(text)
+            .filter_map(|caps| {
+                let range = caps.get(0)?.range();
+                let mut with = String::new();
+                if self.expand {
+                    caps.expand(replacement, &mut with);
+                } else {
+                    with.push_str(replacement);
+                }
+                Some((range, with))
+            })
+            .collect()
+    }
+}
+
+/// `text` with the given non-overlapping, ordered ranges replaced
+pub fn apply(text: &str, replacements: &[Replacement]) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for (range, with) in replacements {
+        result.push_str(&text[last..range.start]);
+        result.push_str(with);
+        last = range.end;
+    }
+    result.push_str(&text[last..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(case_sensitive: bool, whole_word: bool, regex: bool) -> MatchOptions {
+        MatchOptions {
+            case_sensitive,
+            whole_word,
+            regex,
+        }
+    }
+
+    fn matches<'a>(pattern: &str, options: MatchOptions, text: &'a str) -> Vec<&'a str> {
+        let pattern = Pattern::new(pattern, options).unwrap();
+        pattern.find_all(text).into_iter().map(|r| &text[r]).collect()
+    }
+
+    #[test]
+    fn rejects_bad_patterns() {
+        let regex = options(false, false, true);
+        let err = |pattern: &str, options| Pattern::new(pattern, options).err();
+
+        assert_eq!(err("", MatchOptions::default()), Some(PatternError::Empty));
+        assert_eq!(err(&"a".repeat(MAX_PATTERN_LEN + 1), MatchOptions::default()), Some(PatternError::TooLong));
+        assert!(Pattern::new(&"a".repeat(MAX_PATTERN_LEN), MatchOptions::default()).is_ok());
+        assert_eq!(err("a*", regex), Some(PatternError::MatchesEmpty));
+        assert_eq!(err("x|", regex), Some(PatternError::MatchesEmpty));
+        assert_eq!(err(r"\w{1000}\w{1000}", regex), Some(PatternError::TooComplex));
+        assert!(matches!(err("(unclosed", regex), Some(PatternError::Invalid(_))));
+    }
+
+    #[test]
+    fn literal_text_is_escaped() {
+        let text = "cost (a+b) is $5.00, not 5x00";
+        assert_eq!(matches("(a+b)", MatchOptions::default(), text), ["(a+b)"]);
+        assert_eq!(matches("5.00", MatchOptions::default(), text), ["5.00"]);
+        assert_eq!(matches("a*", MatchOptions::default(), "a* aa"), ["a*"]);
+    }
+
+    #[test]
+    fn case_sensitivity() {
+        let text = "Note note NOTE";
+        assert_eq!(matches("note", MatchOptions::default(), text), ["Note", "note", "NOTE"]);
+        assert_eq!(matches("note", options(true, false, false), text), ["note"]);
+        assert_eq!(matches("Ünï", MatchOptions::default(), "ünï ÜNÏ"), ["ünï", "ÜNÏ"]);
+    }
+
+    #[test]
+    fn whole_words() {
+        let text = "cat concat cat's scatter cat";
+        assert_eq!(matches("cat", options(false, true, false), text), ["cat", "cat", "cat"]);
+        assert_eq!(matches("cat|dog", options(false, true, true), "dogma dog cat"), ["dog", "cat"]);
+    }
+
+    #[test]
+    fn regex_replacements_expand_groups() {
+        let pattern = Pattern::new(r"(\w+)@(\w+)", options(false, false, true)).unwrap();
+        let text = "mail a@b and c@d";
+        let replacements = pattern.replacements(text, "$2 at ${1}");
+        assert_eq!(apply(text, &replacements), "mail b at a and d at c");
+    }
+
+    #[test]
+    fn literal_replacements_are_taken_as_is() {
+        let pattern = Pattern::new("(x)", MatchOptions::default()).unwrap();
+        let text = "(x) and (x)";
+        let replacements = pattern.replacements(text, "$1");
+        assert_eq!(replacements.len(), 2);
+        assert_eq!(apply(text, &replacements), "$1 and $1");
+    }
+
+    #[test]
+    fn apply_keeps_text_between_ranges() {
+        let text = "one two three";
+        assert_eq!(apply(text, &[(4..7, "2".to_string())]), "one 2 three");
+        assert_eq!(apply(text, &[]), text);
+        assert_eq!(apply(text, &[(0..3, "1".to_string()), (8..13, "3".to_string())]), "1 two 3");
+    }
+}
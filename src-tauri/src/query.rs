@@ -0,0 +1,355 @@
+use crate::markdown;
+use chrono::NaiveDate;
+use std::fmt;
+
+// The search box syntax. Parts separated by spaces must all match; `OR` between
+// two parts means either will do and binds tighter than the implicit AND, so
+// `budget q1 OR q2` is `budget AND (q1 OR q2)`.
+//
+//   word  pre*  "exact phrase"  -excluded  a OR b
+//   folder:work  tag:meeting  before:2026-01-01  after:2025-12-01
+//   has:audio  has:todo
+//
+// Filter values may be quoted: `folder:"Project notes"`. Anything else with a
+// colon, like a URL, is searched as text.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    /// Every part must match; empty matches everything
+    And(Vec<Query>),
+    /// At least one part must match
+    Or(Vec<Query>),
+    Not(Box<Query>),
+    /// A word, matched on its stem
+    Word(String),
+    /// `pre*`, stored without the `*`
+    Prefix(String),
+    /// `"exact phrase"`
+    Phrase(String),
+    Filter(Filter),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// Notes in this folder or below it, as a vault-relative path
+    Folder(String),
+    /// Notes with this tag or one nested below it, normalized
+    Tag(String),
+    /// Modified before the start of this day
+    Before(NaiveDate),
+    /// Modified on or after this day
+    After(NaiveDate),
+    Has(Has),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Has {
+    /// Linked to a recording
+    Audio,
+    /// Contains an unchecked `- [ ]` task
+    Todo,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    /// `tag:` with nothing after it
+    MissingValue(String),
+    InvalidDate(String),
+    InvalidTag(String),
+    UnknownHas(String),
+    /// `"` without a closing quote
+    UnclosedQuote,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::MissingValue(key) => write!(f, "'{}:' needs a value", key),
+            ParseError::InvalidDate(date) => write!(f, "Invalid date '{}', expected YYYY-MM-DD", date),
+            ParseError::InvalidTag(tag) => write!(f, "Invalid tag '{}'", tag),
+            ParseError::UnknownHas(what) => write!(f, "Unknown 'has:{}', expected audio or todo", what),
+            ParseError::UnclosedQuote => write!(f, "Missing closing quote"),
+        }
+    }
+}
+
+impl From<ParseError> for String {
+    fn from(e: ParseError) -> Self {
+        e.to_string()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    /// A part of the query, `-` already stripped into `negated`
+    Part { negated: bool, key: Option<String>, value: String, quoted: bool },
+    Or,
+}
+
+const FILTER_KEYS: [&str; 5] = ["folder", "tag", "before", "after", "has"];
+
+// Read a `"..."` value at the start of `input`; returns it and the rest
+fn quoted(input: &str) -> Result<(&str, &str), ParseError> {
+    let inner = &input[1..];
+    let end = inner.find('"').ok_or(ParseError::UnclosedQuote)?;
+    Ok((&inner[..end], &inner[end + 1..]))
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut rest = input.trim_start();
+
+    while !rest.is_empty() {
+        let negated = rest.starts_with('-') && rest[1..].starts_with(|c: char| !c.is_whitespace());
+        if negated {
+            rest = &rest[1..];
+        }
+
+        // `key:` of a known filter, possibly followed by a quoted value
+        let key = rest
+            .split_once(':')
+            .map(|(key, _)| key)
+            .filter(|key| FILTER_KEYS.contains(&key.to_lowercase().as_str()));
+        let (key, after_key) = match key {
+            Some(key) => (Some(key.to_lowercase()), &rest[key.len() + 1..]),
+            None => (None, rest),
+        };
+
+        let (value, remaining, is_quoted) = if after_key.starts_with('"') {
+            let (value, remaining) = quoted(after_key)?;
+            (value, remaining, true)
+        } else {
+            let end = after_key.find(char::is_whitespace).unwrap_or(after_key.len());
+            (&after_key[..end], &after_key[end..], false)
+        };
+
+        if key.is_none() && !negated && !is_quoted && value == "OR" {
+            tokens.push(Token::Or);
+        } else {
+            tokens.push(Token::Part {
+                negated,
+                key,
+                value: value.to_string(),
+                quoted: is_quoted,
+            });
+        }
+        rest = remaining.trim_start();
+    }
+
+    Ok(tokens)
+}
+
+fn date(value: &str) -> Result<NaiveDate, ParseError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| ParseError::InvalidDate(value.to_string()))
+}
+
+// One part of the query, or None for parts that match nothing in particular (`*`, `""`)
+fn part(key: Option<String>, value: String, quoted: bool) -> Result<Option<Query>, ParseError> {
+    let key = match key {
+        Some(key) => key,
+        None if quoted => {
+            let phrase = value.trim();
+            return Ok((!phrase.is_empty()).then(|| Query::Phrase(phrase.to_string())));
+        }
+        None => {
+            return Ok(match value.strip_suffix('*') {
+                Some("") => None,
+                Some(prefix) => Some(Query::Prefix(prefix.to_string())),
+                None => Some(Query::Word(value)),
+            });
+        }
+    };
+
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(ParseError::MissingValue(key));
+    }
+
+    let filter = match key.as_str() {
+        "folder" => Filter::Folder(value.trim_matches(['/', '\\']).replace('\\', "/")),
+        "tag" => Filter::Tag(markdown::normalize_tag(value).ok_or_else(|| ParseError::InvalidTag(value.to_string()))?),
+        "before" => Filter::Before(date(value)?),
+        "after" => Filter::After(date(value)?),
+        _ => match value.to_lowercase().as_str() {
+            "audio" | "recording" => Filter::Has(Has::Audio),
+            "todo" | "todos" | "task" | "tasks" => Filter::Has(Has::Todo),
+            _ => return Err(ParseError::UnknownHas(value.to_string())),
+        },
+    };
+    Ok(Some(Query::Filter(filter)))
+}
+
+fn collapse(mut items: Vec<Query>, wrap: fn(Vec<Query>) -> Query) -> Query {
+    if items.len() == 1 {
+        items.pop().unwrap()
+    } else {
+        wrap(items)
+    }
+}
+
+/// Parse the search box text. An empty query is an empty `And`.
+pub fn parse(input: &str) -> Result<Query, ParseError> {
+    // Groups of parts joined by OR, in order
+    let mut groups: Vec<Vec<Query>> = Vec::new();
+    let mut join_next = false;
+
+    for token in tokenize(input)? {
+        match token {
+            // A dangling OR, as while typing `a OR`, is ignored
+            Token::Or => join_next = !groups.is_empty(),
+            Token::Part { negated, key, value, quoted } => {
+                let query = match part(key, value, quoted)? {
+                    Some(query) if negated => Query::Not(Box::new(query)),
+                    Some(query) => query,
+                    None => continue,
+                };
+                match groups.last_mut() {
+                    Some(group) if join_next => group.push(query),
+                    _ => groups.push(vec![query]),
+                }
+                join_next = false;
+            }
+        }
+    }
+
+    let parts = groups.into_iter().map(|group| collapse(group, Query::Or)).collect();
+    Ok(collapse(parts, Query::And))
+}
+
+impl Query {
+    /// Text parts that have to appear in a match (for highlighting), skipping
+    /// anything under a `Not`
+    pub fn positive_text(&self) -> Vec<&Query> {
+        match self {
+            Query::And(items) | Query::Or(items) => items.iter().flat_map(|q| q.positive_text()).collect(),
+            Query::Word(_) | Query::Prefix(_) | Query::Phrase(_) => vec![self],
+            Query::Not(_) | Query::Filter(_) => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(w: &str) -> Query {
+        Query::Word(w.to_string())
+    }
+
+    fn day(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn parses_words_phrases_and_prefixes() {
+        assert_eq!(parse("budget").unwrap(), word("budget"));
+        assert_eq!(
+            parse(r#"budget "next quarter"  plan*"#).unwrap(),
+            Query::And(vec![
+                word("budget"),
+                Query::Phrase("next quarter".to_string()),
+                Query::Prefix("plan".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn empty_queries_match_everything() {
+        assert_eq!(parse("").unwrap(), Query::And(vec![]));
+        assert_eq!(parse("   ").unwrap(), Query::And(vec![]));
+        assert_eq!(parse(r#"* """#).unwrap(), Query::And(vec![]));
+    }
+
+    #[test]
+    fn parses_exclusions() {
+        assert_eq!(
+            parse(r#"notes -draft -"old stuff" -tag:archive"#).unwrap(),
+            Query::And(vec![
+                word("notes"),
+                Query::Not(Box::new(word("draft"))),
+                Query::Not(Box::new(Query::Phrase("old stuff".to_string()))),
+                Query::Not(Box::new(Query::Filter(Filter::Tag("archive".to_string())))),
+            ])
+        );
+        // A lone dash is just a word
+        assert_eq!(parse("a - b").unwrap(), Query::And(vec![word("a"), word("-"), word("b")]));
+    }
+
+    #[test]
+    fn or_binds_tighter_than_and() {
+        assert_eq!(
+            parse("budget q1 OR q2 OR q3 plan").unwrap(),
+            Query::And(vec![
+                word("budget"),
+                Query::Or(vec![word("q1"), word("q2"), word("q3")]),
+                word("plan"),
+            ])
+        );
+        assert_eq!(parse("a OR -b").unwrap(), Query::Or(vec![word("a"), Query::Not(Box::new(word("b")))]));
+    }
+
+    #[test]
+    fn lowercase_or_and_dangling_or_are_not_operators() {
+        assert_eq!(parse("this or that").unwrap(), Query::And(vec![word("this"), word("or"), word("that")]));
+        assert_eq!(parse("OR a OR").unwrap(), word("a"));
+        assert_eq!(parse(r#""OR""#).unwrap(), Query::Phrase("OR".to_string()));
+    }
+
+    #[test]
+    fn parses_filters() {
+        assert_eq!(
+            parse("folder:work/meetings tag:#Project/Alpha has:audio HAS:todo").unwrap(),
+            Query::And(vec![
+                Query::Filter(Filter::Folder("work/meetings".to_string())),
+                Query::Filter(Filter::Tag("project/alpha".to_string())),
+                Query::Filter(Filter::Has(Has::Audio)),
+                Query::Filter(Filter::Has(Has::Todo)),
+            ])
+        );
+        assert_eq!(
+            parse(r#"folder:"Project notes/""#).unwrap(),
+            Query::Filter(Filter::Folder("Project notes".to_string()))
+        );
+    }
+
+    #[test]
+    fn parses_date_filters() {
+        assert_eq!(
+            parse("after:2025-12-01 before:2026-01-01").unwrap(),
+            Query::And(vec![
+                Query::Filter(Filter::After(day(2025, 12, 1))),
+                Query::Filter(Filter::Before(day(2026, 1, 1))),
+            ])
+        );
+    }
+
+    #[test]
+    fn unknown_keys_are_text() {
+        assert_eq!(parse("https://example.com").unwrap(), word("https://example.com"));
+        assert_eq!(parse("note:x").unwrap(), word("note:x"));
+    }
+
+    #[test]
+    fn reports_invalid_filters() {
+        assert_eq!(parse("tag:"), Err(ParseError::MissingValue("tag".to_string())));
+        assert_eq!(parse(r#"folder:"""#), Err(ParseError::MissingValue("folder".to_string())));
+        assert_eq!(parse("before:2026-13-01"), Err(ParseError::InvalidDate("2026-13-01".to_string())));
+        assert_eq!(parse("after:yesterday"), Err(ParseError::InvalidDate("yesterday".to_string())));
+        assert_eq!(parse("tag:123"), Err(ParseError::InvalidTag("123".to_string())));
+        assert_eq!(parse("has:video"), Err(ParseError::UnknownHas("video".to_string())));
+        assert_eq!(parse(r#"budget "next quarter"#), Err(ParseError::UnclosedQuote));
+    }
+
+    #[test]
+    fn collects_positive_text() {
+        let query = parse(r#"a -b "c d" e* OR tag:x"#).unwrap();
+        assert_eq!(
+            query.positive_text(),
+            vec![
+                &word("a"),
+                &Query::Phrase("c d".to_string()),
+                &Query::Prefix("e".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,302 @@
+use crate::atomic;
+use crate::AppState;
+use notify_debouncer_full::notify::event::{ModifyKind, RenameMode};
+use notify_debouncer_full::notify::{EventKind, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, DebouncedEvent, Debouncer, RecommendedCache};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+// Watches the notes directory and tells the webview about changes made outside
+// the app (sync tools, other editors) as well as our own writes.
+
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+pub type VaultWatcher = Debouncer<RecommendedWatcher, RecommendedCache>;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VaultChange {
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ChangeKind {
+    NoteCreated,
+    NoteModified,
+    NoteDeleted,
+    FolderChanged,
+}
+
+impl ChangeKind {
+    fn event_name(self) -> &'static str {
+        match self {
+            ChangeKind::NoteCreated => "note-created",
+            ChangeKind::NoteModified => "note-modified",
+            ChangeKind::NoteDeleted => "note-deleted",
+            ChangeKind::FolderChanged => "folder-changed",
+        }
+    }
+}
+
+fn is_note(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext == "md" || ext == "txt")
+        .unwrap_or(false)
+}
+
+// Relative path of a visible vault entry. Anything under a hidden component
+// (.audio, .onote, .trash, temp files of atomic writes) is ignored. Events may
+// report the directory as given or canonicalized, so both are tried.
+fn visible_path(roots: &[PathBuf], path: &Path) -> Option<String> {
+    let rel = roots.iter().find_map(|root| path.strip_prefix(root).ok())?;
+    let hidden = rel
+        .components()
+        .any(|c| c.as_os_str().to_string_lossy().starts_with('.'));
+    if hidden || rel.as_os_str().is_empty() {
+        None
+    } else {
+        Some(rel.to_string_lossy().to_string())
+    }
+}
+
+// Something appeared at `path`; removed paths can't be inspected, so a missing
+// path without a note extension is taken to be a folder
+fn appeared(path: &Path) -> Option<ChangeKind> {
+    if path.is_dir() {
+        Some(ChangeKind::FolderChanged)
+    } else if is_note(path) {
+        Some(ChangeKind::NoteCreated)
+    } else {
+        None
+    }
+}
+
+fn disappeared(path: &Path) -> Option<ChangeKind> {
+    if is_note(path) {
+        Some(ChangeKind::NoteDeleted)
+    } else if path.extension().is_none() {
+        Some(ChangeKind::FolderChanged)
+    } else {
+        None
+    }
+}
+
+// Notes an atomic write renamed a temp file over in this batch. FSEvents reports
+// such a rename as one `Name(Any)` event per path rather than as a pair.
+fn atomic_targets(events: &[DebouncedEvent]) -> HashSet<PathBuf> {
+    events
+        .iter()
+        .filter(|event| matches!(event.kind, EventKind::Modify(ModifyKind::Name(_))))
+        .flat_map(|event| event.paths.iter())
+        .filter_map(|path| atomic::target_of(path))
+        .collect()
+}
+
+// `replaced` holds the targets of atomic writes from `atomic_targets`
+fn classify<'a>(event: &'a DebouncedEvent, replaced: &HashSet<PathBuf>) -> Vec<(ChangeKind, &'a Path)> {
+    let paths = &event.paths;
+    let mut changes = Vec::new();
+
+    match event.kind {
+        EventKind::Create(_) => {
+            for path in paths {
+                changes.extend(appeared(path).map(|kind| (kind, path.as_path())));
+            }
+        }
+        EventKind::Remove(_) => {
+            for path in paths {
+                changes.extend(disappeared(path).map(|kind| (kind, path.as_path())));
+            }
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if paths.len() == 2 => {
+            let (from, to) = (&paths[0], &paths[1]);
+
+            // An atomic write renames its temp file over the note
+            if atomic::target_of(from).as_deref() == Some(to.as_path()) {
+                changes.push((ChangeKind::NoteModified, to.as_path()));
+            } else {
+                changes.extend(disappeared(from).map(|kind| (kind, from.as_path())));
+                changes.extend(appeared(to).map(|kind| (kind, to.as_path())));
+            }
+        }
+        EventKind::Modify(ModifyKind::Name(_)) => {
+            for path in paths {
+                let kind = if replaced.contains(path) && path.is_file() {
+                    Some(ChangeKind::NoteModified)
+                } else if path.exists() {
+                    appeared(path)
+                } else {
+                    disappeared(path)
+                };
+                changes.extend(kind.map(|kind| (kind, path.as_path())));
+            }
+        }
+        EventKind::Modify(ModifyKind::Data(_)) | EventKind::Modify(ModifyKind::Any) => {
+            for path in paths.iter().filter(|p| is_note(p)) {
+                changes.push((ChangeKind::NoteModified, path.as_path()));
+            }
+        }
+        _ => {}
+    }
+
+    changes
+}
+
+fn handle_events(app: &AppHandle, roots: &[PathBuf], result: DebounceEventResult) {
+    let events = match result {
+        Ok(events) => events,
+        Err(errors) => {
+            for e in errors {
+                eprintln!("Watcher error: {}", e);
+            }
+            return;
+        }
+    };
+
+    // One event per path and kind per batch, in the order they happened
+    let mut changes: Vec<(ChangeKind, String)> = Vec::new();
+    let replaced = atomic_targets(&events);
+    for event in &events {
+        for (kind, path) in classify(event, &replaced) {
+            if let Some(rel) = visible_path(roots, path) {
+                if !changes.iter().any(|(k, p)| *k == kind && *p == rel) {
+                    changes.push((kind, rel));
+                }
+            }
+        }
+    }
+
+    // Keep the note index current before the webview asks for fresh listings
+    if !changes.is_empty() {
+        let state = app.state::<AppState>();
+        let mut index = state.index.lock().unwrap();
+        for (_, path) in &changes {
+            index.update(&roots[0].join(path));
+        }
+    }
+
+    for (kind, path) in changes {
+        app.emit(kind.event_name(), VaultChange { path }).ok();
+    }
+}
+
+/// Start watching `notes_dir`; dropping the returned watcher stops it
+pub fn start(app: &AppHandle, notes_dir: PathBuf) -> Result<VaultWatcher, String> {
+    let handler_app = app.clone();
+    let mut roots = vec![notes_dir.clone()];
+    if let Ok(canonical) = notes_dir.canonicalize() {
+        roots.push(canonical);
+    }
+
+    let mut debouncer = new_debouncer(DEBOUNCE, None, move |result: DebounceEventResult| {
+        handle_events(&handler_app, &roots, result);
+    })
+    .map_err(|e| e.to_string())?;
+
+    debouncer
+        .watch(&notes_dir, RecursiveMode::Recursive)
+        .map_err(|e| e.to_string())?;
+
+    println!("Watching notes directory: {:?}", notes_dir);
+    Ok(debouncer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify_debouncer_full::notify::event::{CreateKind, DataChange, RemoveKind};
+    use notify_debouncer_full::notify::Event;
+    use std::fs;
+    use std::time::Instant;
+    use tempfile::TempDir;
+
+    fn event(kind: EventKind, paths: &[&Path]) -> DebouncedEvent {
+        let event = paths
+            .iter()
+            .fold(Event::new(kind), |event, path| event.add_path(path.to_path_buf()));
+        DebouncedEvent::new(event, Instant::now())
+    }
+
+    fn kinds(events: &[DebouncedEvent]) -> Vec<(ChangeKind, PathBuf)> {
+        let replaced = atomic_targets(events);
+        events
+            .iter()
+            .flat_map(|e| classify(e, &replaced))
+            .map(|(kind, path)| (kind, path.to_path_buf()))
+            .collect()
+    }
+
+    // The temp file name `atomic::write` uses for `path`
+    fn temp_of(path: &Path) -> PathBuf {
+        let name = path.file_name().unwrap().to_string_lossy();
+        path.with_file_name(format!(".{}.onote-tmp-1-0", name))
+    }
+
+    #[test]
+    fn creates_removes_and_edits() {
+        let dir = TempDir::new().unwrap();
+        let note = dir.path().join("a.md");
+        let folder = dir.path().join("sub");
+        fs::write(&note, "").unwrap();
+        fs::create_dir(&folder).unwrap();
+        let gone = dir.path().join("gone.md");
+        let image = dir.path().join("pic.png");
+        fs::write(&image, "").unwrap();
+
+        let events = [
+            event(EventKind::Create(CreateKind::File), &[&note, &image]),
+            event(EventKind::Create(CreateKind::Folder), &[&folder]),
+            event(EventKind::Modify(ModifyKind::Data(DataChange::Content)), &[&note, &image]),
+            event(EventKind::Remove(RemoveKind::Any), &[&gone, &dir.path().join("old-folder")]),
+        ];
+        assert_eq!(
+            kinds(&events),
+            [
+                (ChangeKind::NoteCreated, note.clone()),
+                (ChangeKind::FolderChanged, folder),
+                (ChangeKind::NoteModified, note),
+                (ChangeKind::NoteDeleted, gone),
+                (ChangeKind::FolderChanged, dir.path().join("old-folder")),
+            ]
+        );
+    }
+
+    #[test]
+    fn renames_are_a_delete_and_a_create() {
+        let dir = TempDir::new().unwrap();
+        let from = dir.path().join("old.md");
+        let to = dir.path().join("new.md");
+        fs::write(&to, "").unwrap();
+
+        let events = [event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &[&from, &to])];
+        assert_eq!(kinds(&events), [(ChangeKind::NoteDeleted, from.clone()), (ChangeKind::NoteCreated, to.clone())]);
+
+        // Reported one path at a time
+        let events = [
+            event(EventKind::Modify(ModifyKind::Name(RenameMode::From)), &[&from]),
+            event(EventKind::Modify(ModifyKind::Name(RenameMode::To)), &[&to]),
+        ];
+        assert_eq!(kinds(&events), [(ChangeKind::NoteDeleted, from), (ChangeKind::NoteCreated, to)]);
+    }
+
+    #[test]
+    fn atomic_saves_are_modifications() {
+        let dir = TempDir::new().unwrap();
+        let note = dir.path().join("a.md");
+        fs::write(&note, "").unwrap();
+        let temp = temp_of(&note);
+
+        // inotify pairs the two paths
+        let events = [event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &[&temp, &note])];
+        assert_eq!(kinds(&events), [(ChangeKind::NoteModified, note.clone())]);
+
+        // FSEvents reports each path on its own
+        let events = [
+            event(EventKind::Modify(ModifyKind::Name(RenameMode::Any)), &[&temp]),
+            event(EventKind::Modify(ModifyKind::Name(RenameMode::Any)), &[&note]),
+        ];
+        assert_eq!(kinds(&events), [(ChangeKind::NoteModified, note)]);
+    }
+}
@@ -1,4 +1,5 @@
-import { readNote, saveNote } from "../utils/tauri-commands";
+import { listen } from "@tauri-apps/api/event";
+import { readNote, recordNoteOpen, saveNote, type SaveError, type VaultChange } from "../utils/tauri-commands";
 import { notesStore } from "./notes.svelte";
 
 // State
@@ -9,12 +10,17 @@ let isSaving = $state(false);
 let cursorPosition = $state(0);
 let wordCount = $state(0);
 let lastSavedTitle = $state("");
+// Version token of the content on disk, for conflict detection
+let version = $state<string | null>(null);
+// Set when a save was refused because the file changed on disk
+let conflict = $state<Extract<SaveError, { kind: "conflict" }> | null>(null);
 
 let saveTimeout: ReturnType<typeof setTimeout>;
 
-// Helper to extract title (first line)
+// Helper to extract title (first line after any YAML frontmatter)
 function getTitle(text: string): string {
-  return text.split('\n')[0].replace(/^#*\s*/, '').trim();
+  const body = text.replace(/^---\r?\n[\s\S]*?\r?\n(?:---|\.\.\.)\r?\n/, '');
+  return body.split('\n')[0].replace(/^#*\s*/, '').trim();
 }
 
 // Derived
@@ -27,9 +33,12 @@ async function loadNote(notePath: string) {
   try {
     const result = await readNote(notePath);
     content = result.content;
+    version = result.version;
+    conflict = null;
     wordCount = content.split(/\s+/).filter(Boolean).length;
     lastSavedTitle = getTitle(result.content);
     isDirty = false;
+    recordNoteOpen(notePath).catch((error) => console.error("Failed to record note open:", error));
   } catch (error) {
     console.error("Failed to load note:", error);
     path = null; // Reset path if load fails
@@ -60,22 +69,69 @@ function updateContent(newContent: string) {
 }
 
 async function save() {
-  if (!path || !isDirty) return;
+  if (!path || !isDirty || conflict) return;
 
   isSaving = true;
   try {
-    await saveNote(path, content);
+    const result = await saveNote(path, content, version ?? undefined);
+    // On a conflict copy, keep editing the copy. The note may also have been
+    // renamed to follow its title.
+    if (result.path !== path && notesStore.selectedNoteId === path) {
+      notesStore.selectNote(result.path);
+    }
+    path = result.path;
+    version = result.version;
     isDirty = false;
     lastSavedTitle = getTitle(content);
     // Refresh notes list to update title/preview in sidebar
     await notesStore.loadNotes(notesStore.selectedFolder);
   } catch (error) {
+    const saveError = error as SaveError;
+    if (saveError?.kind === "conflict") {
+      conflict = saveError;
+    }
     console.error("Failed to save note:", error);
   } finally {
     isSaving = false;
   }
 }
 
+// Pick up edits made by other apps, unless there are unsaved changes here
+async function reloadIfChanged(notePath: string) {
+  if (notePath !== path || isDirty || isSaving) return;
+
+  try {
+    const result = await readNote(notePath);
+    if (result.version === version || notePath !== path || isDirty) return;
+    content = result.content;
+    version = result.version;
+    wordCount = content.split(/\s+/).filter(Boolean).length;
+    lastSavedTitle = getTitle(content);
+  } catch (error) {
+    console.error("Failed to reload note:", error);
+  }
+}
+
+listen<VaultChange>("note-modified", ({ payload }) => reloadIfChanged(payload.path));
+
+// Resolve a refused save: overwrite the disk version with ours, or take theirs
+async function resolveConflict(keepMine: boolean) {
+  if (!conflict) return;
+
+  if (keepMine) {
+    version = conflict.disk_version;
+    conflict = null;
+    await save();
+  } else {
+    content = conflict.disk_content;
+    version = conflict.disk_version;
+    wordCount = content.split(/\s+/).filter(Boolean).length;
+    lastSavedTitle = getTitle(content);
+    isDirty = false;
+    conflict = null;
+  }
+}
+
 function updateCursor(position: number) {
   cursorPosition = position;
 }
@@ -98,6 +154,8 @@ function clear() {
   clearTimeout(saveTimeout);
   content = "";
   path = null;
+  version = null;
+  conflict = null;
   isDirty = false;
   isSaving = false;
   cursorPosition = 0;
@@ -124,9 +182,13 @@ export const editorStore = {
   get wordCount() {
     return wordCount;
   },
+  get conflict() {
+    return conflict;
+  },
   loadNote,
   updateContent,
   save,
+  resolveConflict,
   updateCursor,
   insertAtCursor,
   clear,
@@ -1,12 +1,19 @@
+import { listen } from "@tauri-apps/api/event";
 import {
   listFolders,
   listNotes,
+  listView,
   createNote,
   deleteNote,
   createFolder,
   deleteFolder,
+  setNotePinned,
+  reorderNotes,
   type FolderInfo,
   type NoteMeta,
+  type NoteSort,
+  type NoteView,
+  type VaultChange,
 } from "../utils/tauri-commands";
 
 // State
@@ -15,6 +22,10 @@ let notes = $state<NoteMeta[]>([]);
 let selectedFolder = $state<string>("inbox");
 let selectedNoteId = $state<string | null>(null);
 let isLoading = $state(false);
+let sort = $state<NoteSort>("modified");
+// A virtual view across folders, shown instead of the selected folder
+let selectedView = $state<NoteView | null>(null);
+let viewTotal = $state(0);
 
 // Actions
 async function loadFolders() {
@@ -32,8 +43,9 @@ async function loadFolders() {
 async function loadNotes(folder: string) {
   isLoading = true;
   try {
-    notes = await listNotes(folder);
+    notes = await listNotes(folder, sort);
     selectedFolder = folder;
+    selectedView = null;
     // Select first note if any
     if (notes.length > 0 && !selectedNoteId) {
       selectedNoteId = notes[0].id;
@@ -46,6 +58,37 @@ async function loadNotes(folder: string) {
   }
 }
 
+async function loadView(view: NoteView) {
+  isLoading = true;
+  try {
+    const page = await listView(view, { sort });
+    notes = page.notes;
+    viewTotal = page.total;
+    selectedView = view;
+  } catch (error) {
+    console.error("Failed to load view:", error);
+    notes = [];
+  } finally {
+    isLoading = false;
+  }
+}
+
+// Next page of the current view
+async function loadMore() {
+  if (!selectedView || notes.length >= viewTotal) return;
+  try {
+    const page = await listView(selectedView, { sort, offset: notes.length });
+    notes = [...notes, ...page.notes];
+    viewTotal = page.total;
+  } catch (error) {
+    console.error("Failed to load more notes:", error);
+  }
+}
+
+function reload() {
+  return selectedView ? loadView(selectedView) : loadNotes(selectedFolder);
+}
+
 async function addNote(title?: string) {
   try {
     const path = await createNote(selectedFolder, title);
@@ -61,7 +104,7 @@ async function addNote(title?: string) {
 async function removeNote(path: string) {
   try {
     await deleteNote(path);
-    await loadNotes(selectedFolder);
+    await reload();
     if (selectedNoteId === path) {
       selectedNoteId = notes.length > 0 ? notes[0].id : null;
     }
@@ -79,6 +122,23 @@ function selectFolder(path: string) {
   loadNotes(path);
 }
 
+async function togglePinned(note: NoteMeta) {
+  await setNotePinned(note.path, !note.pinned);
+  await reload();
+}
+
+// Save a dragged arrangement and show the folder in it
+async function reorder(paths: string[]) {
+  await reorderNotes(selectedFolder, paths);
+  sort = "manual";
+  await loadNotes(selectedFolder);
+}
+
+function setSort(value: NoteSort) {
+  sort = value;
+  reload();
+}
+
 async function addFolder(name: string, parent?: string) {
   try {
     const path = await createFolder(name, parent);
@@ -104,6 +164,29 @@ async function removeFolder(path: string) {
   }
 }
 
+// Keep the lists in sync with changes on disk, including other apps' edits
+let refreshTimeout: ReturnType<typeof setTimeout>;
+
+function parentFolder(path: string): string {
+  const i = Math.max(path.lastIndexOf("/"), path.lastIndexOf("\\"));
+  return i === -1 ? "" : path.slice(0, i);
+}
+
+function scheduleRefresh(reloadFolders: boolean) {
+  clearTimeout(refreshTimeout);
+  refreshTimeout = setTimeout(async () => {
+    if (reloadFolders) await loadFolders();
+    await reload();
+  }, 200);
+}
+
+listen<VaultChange>("folder-changed", () => scheduleRefresh(true));
+for (const event of ["note-created", "note-modified", "note-deleted"]) {
+  listen<VaultChange>(event, ({ payload }) => {
+    if (selectedView || parentFolder(payload.path) === selectedFolder) scheduleRefresh(false);
+  });
+}
+
 // Initialize
 loadFolders();
 loadNotes(selectedFolder);
@@ -125,12 +208,26 @@ export const notesStore = {
   get isLoading() {
     return isLoading;
   },
+  get sort() {
+    return sort;
+  },
+  get selectedView() {
+    return selectedView;
+  },
+  get viewTotal() {
+    return viewTotal;
+  },
   loadFolders,
   loadNotes,
+  loadView,
+  loadMore,
   addNote,
   removeNote,
   selectNote,
   selectFolder,
+  setSort,
+  togglePinned,
+  reorder,
   addFolder,
   removeFolder,
 };
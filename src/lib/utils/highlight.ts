@@ -0,0 +1,21 @@
+import type { TextRange } from "./tauri-commands";
+
+export interface HighlightPart {
+  text: string;
+  hit: boolean;
+}
+
+// Split text into plain and highlighted parts; ranges are UTF-8 byte offsets
+export function highlightParts(text: string, ranges: TextRange[]): HighlightPart[] {
+  const bytes = new TextEncoder().encode(text);
+  const decoder = new TextDecoder();
+  const parts: HighlightPart[] = [];
+  let pos = 0;
+  for (const range of ranges) {
+    if (range.start > pos) parts.push({ text: decoder.decode(bytes.slice(pos, range.start)), hit: false });
+    parts.push({ text: decoder.decode(bytes.slice(range.start, range.end)), hit: true });
+    pos = range.end;
+  }
+  if (pos < bytes.length) parts.push({ text: decoder.decode(bytes.slice(pos)), hit: false });
+  return parts;
+}
@@ -7,35 +7,223 @@ export interface FolderInfo {
   children: FolderInfo[];
 }
 
+export interface MergeResult {
+  path: string;
+  moved: number;
+  renamed: string[];
+}
+
+export type NoteSort = "modified" | "created" | "title" | "word_count" | "manual";
+
+export type NoteView = "all" | "recent" | "recorded" | "untitled";
+
 export interface NoteMeta {
   id: string;
   path: string;
   title: string;
   preview: string;
   modified: string;
+  created: string | null;
   word_count: number;
+  tags: string[];
+  frontmatter: Frontmatter | null;
+  pinned: boolean;
+}
+
+export interface NotePage {
+  notes: NoteMeta[];
+  total: number;
+  offset: number;
+}
+
+export interface Backlink {
+  path: string;
+  title: string;
+  matches: BacklinkContext[];
+}
+
+export interface BacklinkContext {
+  line_number: number;
+  line_content: string;
+}
+
+export interface TagInfo {
+  name: string;
+  label: string;
+  count: number;
+  children: TagInfo[];
+}
+
+// YAML frontmatter; keys onote doesn't know about are kept in `extra`
+export interface Frontmatter {
+  title: string | null;
+  created: string | null;
+  tags: string[];
+  aliases: string[];
+  source_recording: string | null;
+  extra: Record<string, unknown>;
 }
 
 export interface NoteContent {
   path: string;
   content: string;
+  version: string;
+  frontmatter: Frontmatter | null;
+}
+
+// Payload of the note-created, note-modified, note-deleted and folder-changed events
+export interface VaultChange {
+  path: string;
+}
+
+export interface SaveResult {
+  path: string;
+  version: string;
+  conflict_copy: boolean;
+}
+
+// Rejection value of saveNote
+export type SaveError =
+  | {
+      kind: "conflict";
+      path: string;
+      disk_content: string;
+      disk_version: string;
+      attempted_content: string;
+    }
+  | { kind: "failed"; message: string };
+
+export type ConflictStrategy = "refuse" | "conflict_copy";
+
+// Byte offsets into a UTF-8 string, as returned by Rust
+export interface TextRange {
+  start: number;
+  end: number;
+}
+
+export interface SearchResults {
+  results: SearchResult[];
+  // False if a pattern search left notes out for their size or timed out
+  complete: boolean;
 }
 
 export interface SearchResult {
   path: string;
   title: string;
-  matches: SearchMatch[];
+  score: number;
+  title_highlights: TextRange[];
+  snippets: SearchSnippet[];
+}
+
+// Exact matching instead of ranked search; all off by default
+export interface MatchOptions {
+  case_sensitive: boolean;
+  whole_word: boolean;
+  regex: boolean;
+}
+
+export interface ReplacePreview {
+  files: ReplaceFile[];
+  replacements: number;
+  complete: boolean;
+}
+
+export interface ReplaceFile {
+  path: string;
+  title: string;
+  version: string;
+  changes: ReplaceChange[];
 }
 
-export interface SearchMatch {
+export interface ReplaceChange {
   line_number: number;
-  line_content: string;
+  before: string;
+  after: string;
+}
+
+export interface ReplaceTarget {
+  path: string;
+  version: string;
+}
+
+export interface ReplaceSummary {
+  backup_id: string | null;
+  changed: string[];
+  replacements: number;
+  skipped: string[];
+}
+
+export interface ReplaceBackup {
+  id: string;
+  created_at: string;
+  query: string;
+  replacement: string;
+  files: { path: string; replaced_version: string }[];
+}
+
+export interface UndoSummary {
+  restored: string[];
+  skipped: string[];
+}
+
+export interface QuickOpenResult {
+  path: string;
+  title: string;
+  score: number;
+  title_highlights: TextRange[];
+  path_highlights: TextRange[];
+}
+
+export interface SearchSnippet {
+  line_number: number;
+  text: string;
+  highlights: TextRange[];
 }
 
 export interface AppSettings {
   notes_dir: string;
   model: string;
   font_size: number;
+  audio_retention: AudioRetention;
+  audio_format: AudioFormat;
+  transcript_cache_mb: number;
+  trash_retention_days: number;
+  conflict_strategy: ConflictStrategy;
+  semantic_model: string | null;
+  sync_filename_to_title: boolean;
+}
+
+export type AudioFormat = "wav" | "flac" | "opus";
+
+export type RetentionMode = "keep_all" | "delete_after_transcription" | "limited";
+
+export interface AudioRetention {
+  mode: RetentionMode;
+  max_age_days: number | null;
+  max_size_gb: number | null;
+  remove_orphans: boolean;
+}
+
+export interface RecordingInfo {
+  file_name: string;
+  path: string;
+  size_bytes: number;
+  modified: string;
+  referenced: boolean;
+}
+
+export interface StorageReport {
+  audio_dir: string;
+  total_bytes: number;
+  recording_count: number;
+  orphan_count: number;
+  orphan_bytes: number;
+  recordings: RecordingInfo[];
+}
+
+export interface CleanupResult {
+  deleted: string[];
+  freed_bytes: number;
 }
 
 export interface AudioDevice {
@@ -58,6 +246,99 @@ export interface ModelStatus {
   size_mb: number;
 }
 
+export interface EmbeddingModel {
+  id: string;
+  name: string;
+  size_mb: number;
+  description: string;
+  downloaded: boolean;
+}
+
+export interface SemanticResult {
+  path: string;
+  title: string;
+  score: number;
+  line_number: number;
+  excerpt: string;
+}
+
+// Payload of "semantic-index-progress" while changed notes are embedded
+export interface SemanticIndexProgress {
+  current: number;
+  total: number;
+}
+
+export interface TranscribeOptions {
+  model_id: string;
+  language?: string | null;
+  prompt?: string | null;
+}
+
+export type DiffKind = "equal" | "insert" | "delete";
+
+export interface DiffChunk {
+  kind: DiffKind;
+  text: string;
+}
+
+export interface RetranscribeResult {
+  audio_path: string;
+  text: string;
+  diff: DiffChunk[] | null;
+}
+
+export type BatchStatus = "transcribing" | "done" | "skipped" | "failed";
+
+export interface BatchProgress {
+  current: number;
+  total: number;
+  file: string;
+  status: BatchStatus;
+  note_path: string | null;
+  error: string | null;
+}
+
+export interface BatchFailure {
+  file: string;
+  error: string;
+}
+
+export interface BatchSummary {
+  created: string[];
+  skipped: number;
+  failed: BatchFailure[];
+}
+
+export interface TranscriptCacheInfo {
+  entries: number;
+  total_bytes: number;
+  limit_bytes: number;
+}
+
+export interface RecoveryFile {
+  temp_path: string;
+  note_path: string;
+  size_bytes: number;
+  modified: string;
+  note_exists: boolean;
+  preview: string;
+}
+
+export interface NoteVersion {
+  id: string;
+  saved_at: string;
+  size_bytes: number;
+}
+
+export interface TrashEntry {
+  id: string;
+  name: string;
+  original_path: string;
+  deleted_at: string;
+  is_folder: boolean;
+  size_bytes: number;
+}
+
 export interface DownloadProgress {
   model_id: string;
   downloaded_bytes: number;
@@ -70,16 +351,28 @@ export async function listFolders(): Promise<FolderInfo[]> {
   return invoke<FolderInfo[]>("list_folders");
 }
 
-export async function listNotes(folder: string): Promise<NoteMeta[]> {
-  return invoke<NoteMeta[]>("list_notes", { folder });
+export async function listNotes(folder: string, sort?: NoteSort): Promise<NoteMeta[]> {
+  return invoke<NoteMeta[]>("list_notes", { folder, sort });
+}
+
+export async function listView(
+  view: NoteView,
+  options?: { folder?: string; sort?: NoteSort; offset?: number; limit?: number }
+): Promise<NotePage> {
+  return invoke<NotePage>("list_view", { view, ...options });
 }
 
 export async function readNote(path: string): Promise<NoteContent> {
   return invoke<NoteContent>("read_note", { path });
 }
 
-export async function saveNote(path: string, content: string): Promise<void> {
-  return invoke("save_note", { path, content });
+export async function saveNote(
+  path: string,
+  content: string,
+  expectedVersion?: string,
+  frontmatter?: Frontmatter
+): Promise<SaveResult> {
+  return invoke<SaveResult>("save_note", { path, content, expectedVersion, frontmatter });
 }
 
 export async function createNote(folder: string, title?: string): Promise<string> {
@@ -90,8 +383,12 @@ export async function deleteNote(path: string): Promise<void> {
   return invoke("delete_note", { path });
 }
 
-export async function searchNotes(query: string): Promise<SearchResult[]> {
-  return invoke<SearchResult[]>("search_notes", { query });
+export async function searchNotes(
+  query: string,
+  limit?: number,
+  options?: MatchOptions
+): Promise<SearchResults> {
+  return invoke<SearchResults>("search_notes", { query, limit, options });
 }
 
 export async function createFolder(name: string, parent?: string): Promise<string> {
@@ -106,6 +403,136 @@ export async function renameFolder(oldPath: string, newName: string): Promise<st
   return invoke<string>("rename_folder", { oldPath, newName });
 }
 
+export async function moveFolder(path: string, newParent: string): Promise<string> {
+  return invoke<string>("move_folder", { path, newParent });
+}
+
+export async function mergeFolders(source: string, target: string): Promise<MergeResult> {
+  return invoke<MergeResult>("merge_folders", { source, target });
+}
+
+export async function duplicateFolder(path: string): Promise<string> {
+  return invoke<string>("duplicate_folder", { path });
+}
+
+export async function moveNote(path: string, folder: string): Promise<string> {
+  return invoke<string>("move_note", { path, folder });
+}
+
+export async function renameNote(path: string, newName: string): Promise<string> {
+  return invoke<string>("rename_note", { path, newName });
+}
+
+// Order commands
+export async function setNotePinned(path: string, pinned: boolean): Promise<void> {
+  return invoke("set_note_pinned", { path, pinned });
+}
+
+export async function reorderNotes(folder: string, notes: string[]): Promise<void> {
+  return invoke("reorder_notes", { folder, notes });
+}
+
+// Replace commands
+export async function previewReplace(
+  query: string,
+  replacement: string,
+  options?: MatchOptions
+): Promise<ReplacePreview> {
+  return invoke<ReplacePreview>("preview_replace", { query, replacement, options });
+}
+
+export async function replaceInNotes(
+  query: string,
+  replacement: string,
+  files: ReplaceTarget[],
+  options?: MatchOptions
+): Promise<ReplaceSummary> {
+  return invoke<ReplaceSummary>("replace_in_notes", { query, replacement, options, files });
+}
+
+export async function listReplaceBackups(): Promise<ReplaceBackup[]> {
+  return invoke<ReplaceBackup[]>("list_replace_backups");
+}
+
+export async function undoReplace(backupId: string): Promise<UndoSummary> {
+  return invoke<UndoSummary>("undo_replace", { backupId });
+}
+
+// Recovery commands
+export async function listRecoveryFiles(): Promise<RecoveryFile[]> {
+  return invoke<RecoveryFile[]>("list_recovery_files");
+}
+
+export async function resolveRecoveryFile(tempPath: string, restore: boolean): Promise<string | null> {
+  return invoke<string | null>("resolve_recovery_file", { tempPath, restore });
+}
+
+// History commands
+export async function listVersions(path: string): Promise<NoteVersion[]> {
+  return invoke<NoteVersion[]>("list_versions", { path });
+}
+
+export async function readVersion(path: string, id: string): Promise<string> {
+  return invoke<string>("read_version", { path, id });
+}
+
+export async function diffVersions(path: string, from: string, to?: string): Promise<DiffChunk[]> {
+  return invoke<DiffChunk[]>("diff_versions", { path, from, to });
+}
+
+export async function restoreVersion(path: string, id: string): Promise<NoteContent> {
+  return invoke<NoteContent>("restore_version", { path, id });
+}
+
+// Link commands
+export async function getBacklinks(path: string): Promise<Backlink[]> {
+  return invoke<Backlink[]>("get_backlinks", { path });
+}
+
+export async function resolveLink(target: string, from?: string): Promise<string | null> {
+  return invoke<string | null>("resolve_link", { target, from });
+}
+
+// Quick open commands
+export async function quickOpen(query: string, limit?: number): Promise<QuickOpenResult[]> {
+  return invoke<QuickOpenResult[]>("quick_open", { query, limit });
+}
+
+export async function recordNoteOpen(path: string): Promise<void> {
+  return invoke("record_note_open", { path });
+}
+
+// Index commands
+export async function rebuildIndex(): Promise<number> {
+  return invoke<number>("rebuild_index");
+}
+
+// Tag commands
+export async function listTags(): Promise<TagInfo[]> {
+  return invoke<TagInfo[]>("list_tags");
+}
+
+export async function listNotesByTag(tag: string, includeNested?: boolean): Promise<NoteMeta[]> {
+  return invoke<NoteMeta[]>("list_notes_by_tag", { tag, includeNested });
+}
+
+// Trash commands
+export async function listTrash(): Promise<TrashEntry[]> {
+  return invoke<TrashEntry[]>("list_trash");
+}
+
+export async function restoreFromTrash(id: string): Promise<string> {
+  return invoke<string>("restore_from_trash", { id });
+}
+
+export async function emptyTrash(): Promise<number> {
+  return invoke<number>("empty_trash");
+}
+
+export async function purgeTrash(): Promise<number> {
+  return invoke<number>("purge_trash");
+}
+
 // Settings commands
 export async function getSettings(): Promise<AppSettings> {
   return invoke<AppSettings>("get_settings");
@@ -115,6 +542,19 @@ export async function saveSettings(settings: AppSettings): Promise<void> {
   return invoke("save_settings", { settings });
 }
 
+// Storage commands
+export async function storageReport(): Promise<StorageReport> {
+  return invoke<StorageReport>("storage_report");
+}
+
+export async function applyAudioRetention(): Promise<CleanupResult> {
+  return invoke<CleanupResult>("apply_audio_retention");
+}
+
+export async function deleteOrphanedRecordings(): Promise<CleanupResult> {
+  return invoke<CleanupResult>("delete_orphaned_recordings");
+}
+
 // Audio device commands
 export async function listAudioDevices(): Promise<AudioDevice[]> {
   return invoke<AudioDevice[]>("list_audio_devices");
@@ -170,6 +610,51 @@ export async function transcribe(audioPath: string): Promise<string> {
   return invoke<string>("transcribe", { audioPath });
 }
 
+export async function retranscribe(
+  options: TranscribeOptions,
+  source: { audioPath?: string; notePath?: string }
+): Promise<RetranscribeResult> {
+  return invoke<RetranscribeResult>("retranscribe", {
+    audioPath: source.audioPath,
+    notePath: source.notePath,
+    options,
+  });
+}
+
+export async function batchTranscribe(
+  sourceDir: string,
+  targetFolder: string,
+  options?: TranscribeOptions
+): Promise<BatchSummary> {
+  return invoke<BatchSummary>("batch_transcribe", { sourceDir, targetFolder, options });
+}
+
+export async function transcriptCacheInfo(): Promise<TranscriptCacheInfo> {
+  return invoke<TranscriptCacheInfo>("transcript_cache_info");
+}
+
+export async function clearTranscriptCache(): Promise<void> {
+  return invoke("clear_transcript_cache");
+}
+
 export async function deleteModel(modelId: string): Promise<void> {
   return invoke("delete_model", { modelId });
 }
+
+// Semantic search commands
+export async function semanticSearch(query: string, limit?: number): Promise<SemanticResult[]> {
+  return invoke<SemanticResult[]>("semantic_search", { query, limit });
+}
+
+export async function listEmbeddingModels(): Promise<EmbeddingModel[]> {
+  return invoke<EmbeddingModel[]>("list_embedding_models");
+}
+
+// Progress comes as "embedding-download-progress" events
+export async function downloadEmbeddingModel(modelId: string): Promise<void> {
+  return invoke("download_embedding_model", { modelId });
+}
+
+export async function deleteEmbeddingModel(modelId: string): Promise<void> {
+  return invoke("delete_embedding_model", { modelId });
+}